@@ -0,0 +1,291 @@
+use super::*;
+
+use bevy::app::AppExit;
+use launch_options::LaunchOptions;
+use inventory::InventoryPanel;
+
+//------------------------------------------------------------------------------
+
+//アプリの状態
+#[derive( States, Clone, Copy, PartialEq, Eq, Hash, Debug, Default )]
+pub enum AppState
+{   #[default]
+    Title,       //タイトル画面
+    SceneSelect, //シーンの選択
+    Viewer,      //シーンの表示
+    Paused,      //シーンの表示の一時停止
+}
+
+//ビューアで表示できるシーン
+#[derive( Clone, PartialEq )]
+pub enum ViewerScene
+{   LockedChest,            //宝箱(シーンファイル)
+    TreasureHunt ( usize ), //宝探しモード(宝箱の数)
+    Hello,                  //2Dテキストのhello, world
+    MoveHello,              //動く2Dテキストのhello, world
+    Model ( String ),       //glTFモデル(assetsフォルダからの相対パスか絶対パス)
+}
+
+impl ViewerScene
+{   //シーンの選択画面に表示する名前
+    pub fn name( &self ) -> String
+    {   match self
+        {   Self::LockedChest            => "Treasure chest".to_string(),
+            Self::TreasureHunt ( count ) => format!( "Treasure hunt ({count} chests)" ),
+            Self::Hello                  => "Hello, world! (2D text)".to_string(),
+            Self::MoveHello              => "Moving hello, world! (2D text)".to_string(),
+            Self::Model ( path )         => format!( "Model: {path}" ),
+        }
+    }
+}
+
+//シーンの一覧と選択中のシーン
+#[derive( Resource, Default )]
+pub struct SceneList
+{   pub scenes: Vec<ViewerScene>,
+    pub cursor: usize, //選択中のシーンの番号
+    is_loaded : bool,  //選択中のシーンをspawnしたか
+}
+
+impl SceneList
+{   //選択中のシーン
+    pub fn selected( &self ) -> Option<&ViewerScene>
+    {   self.scenes.get( self.cursor )
+    }
+}
+
+//シーンを切り替える時にまとめてdespawnするエンティティ(親子関係のルート)に付けるマーカー
+#[derive( Component, Reflect )]
+#[reflect( Component )]
+pub struct SceneEntity;
+
+//タイトル・シーン選択・一時停止の画面のテキストに付けるマーカー
+#[derive( Component )]
+pub struct MenuText;
+
+//------------------------------------------------------------------------------
+
+//シーンの一覧を作る(起動オプションでシーンが決まっていればビューアから始める)
+pub fn setup_scenes
+(   launch: Res<LaunchOptions>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut cmds: Commands,
+)
+{   let mut scenes = vec!
+    [   ViewerScene::LockedChest,
+        ViewerScene::TreasureHunt ( launch.hunt.unwrap_or( HUNT_CHEST_COUNT ) ),
+        ViewerScene::Hello,
+        ViewerScene::MoveHello,
+    ];
+
+    //assets/modelsフォルダのglTFファイル(フォルダがなければ何もしない)
+    let mut models = Vec::new();
+    if let Ok ( entries ) = std::fs::read_dir( ron_file::asset_path( MODELS_DIR ) )
+    {   for entry in entries.flatten()
+        {   let file = entry.file_name().to_string_lossy().to_string();
+            let lower = file.to_lowercase();
+            if lower.ends_with( ".gltf" ) || lower.ends_with( ".glb" )
+            {   models.push( format!( "{MODELS_DIR}/{file}" ) );
+            }
+        }
+    }
+    models.sort();
+    if let Some ( model ) = &launch.model
+    {   if ! models.contains( model ) { models.insert( 0, model.clone() ) }
+    }
+    scenes.extend( models.into_iter().map( ViewerScene::Model ) );
+
+    //起動オプションで指定されたシーン
+    let launch_scene = match ( &launch.model, launch.hunt )
+    {   ( Some ( model ), _ ) => Some ( ViewerScene::Model ( model.clone() ) ),
+        ( None, Some ( _ ) ) => Some ( scenes[ 1 ].clone() ),
+        ( None, None ) => None,
+    };
+    let cursor = launch_scene.as_ref()
+        .and_then( | launch_scene | scenes.iter().position( | scene | scene == launch_scene ) )
+        .unwrap_or( 0 );
+    if launch_scene.is_some() { next_state.set( AppState::Viewer ) }
+
+    cmds.insert_resource( SceneList { scenes, cursor, is_loaded: false } );
+}
+
+//選択中のシーンがまだspawnされていないか(一時停止から戻った時はspawnしない)
+pub fn is_scene_unloaded( list: Res<SceneList> ) -> bool
+{   ! list.is_loaded
+}
+
+//選択中のシーンをspawnしたことを記録する
+pub fn mark_scene_loaded( mut list: ResMut<SceneList> )
+{   list.is_loaded = true;
+}
+
+//シーンを表示しているか(一時停止中を含む)
+pub fn in_viewer( state: Res<State<AppState>> ) -> bool
+{   matches!( state.get(), AppState::Viewer | AppState::Paused )
+}
+
+//------------------------------------------------------------------------------
+
+//シーンのエンティティをdespawnして、カメラとシーンごとの情報を初期状態へ戻す
+pub fn unload_scene
+(   q_scene: Query<Entity, With<SceneEntity>>,
+    mut q_camera: Query<( &mut OrbitCamera, &mut Projection )>,
+    mut q_board: Query<&mut Text, With<DisplayBoard>>,
+    mut q_panel: Query<&mut Visibility, With<InventoryPanel>>,
+    mut list: ResMut<SceneList>,
+    mut history: ResMut<camera_history::CameraHistory>,
+    mut key_ring: ResMut<lock_key::KeyRing>,
+    mut cmds: Commands,
+)
+{   for entity in q_scene.iter() { cmds.entity( entity ).despawn_recursive(); }
+    list.is_loaded = false;
+
+    //シーンごとのリソース
+    cmds.remove_resource::<treasure_hunt::HuntRound>();
+    cmds.remove_resource::<model_viewer::ModelInfo>();
+    *history = default();
+    key_ring.key_ids.clear();
+
+    //カメラ
+    for ( mut camera, mut projection ) in q_camera.iter_mut()
+    {   *camera = OrbitCamera::default();
+        *projection = PerspectiveProjection { fov: CAMERA_INIT_FOV, ..default() }.into();
+    }
+
+    //HUD
+    for mut text in q_board.iter_mut() { text.sections[ 0 ].value.clear(); }
+    for mut visibility in q_panel.iter_mut() { *visibility = Visibility::Hidden; }
+}
+
+//------------------------------------------------------------------------------
+
+//画面の中央にメニューのテキストを作る
+fn spawn_menu_text( cmds: &mut Commands, sections: Vec<TextSection> )
+{   let text = Text::from_sections( sections ).with_alignment( TextAlignment::Center );
+    cmds.spawn( ( Text2dBundle { text, ..default() }, MenuText ) );
+}
+
+//メニューのテキストの書式
+fn menu_style( color: Color ) -> TextStyle
+{   TextStyle { font_size: MENU_FONT_SIZE, color, ..default() }
+}
+
+//メニューのテキストを消す
+pub fn despawn_menu
+(   q_menu: Query<Entity, With<MenuText>>,
+    mut cmds: Commands,
+)
+{   for entity in q_menu.iter() { cmds.entity( entity ).despawn_recursive(); }
+}
+
+//タイトル画面を作る
+pub fn spawn_title( mut cmds: Commands )
+{   let sections = vec!
+    [   TextSection::new( "Treasure Chest Viewer\n\n", menu_style( Color::GOLD ) ),
+        TextSection::new( "Press [Enter] to start\n[Esc] to quit", menu_style( Color::WHITE ) ),
+    ];
+    spawn_menu_text( &mut cmds, sections );
+}
+
+//タイトル画面のキー操作
+//  [Enter]:シーンの選択へ [Esc]:終了
+pub fn title_input
+(   inkey: Res<Input<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut e_exit: EventWriter<AppExit>,
+)
+{   //[Alt]+[Enter]はフルスクリーンの切換なので無視する
+    let is_alt = inkey.any_pressed( [ KeyCode::AltLeft, KeyCode::AltRight ] );
+
+    if inkey.just_pressed( KeyCode::Return ) && ! is_alt { next_state.set( AppState::SceneSelect ) }
+    if inkey.just_pressed( KeyCode::Escape ) { e_exit.send( AppExit ) }
+}
+
+//シーンの選択画面を作る(中身はshow_scene_selectで書く)
+pub fn spawn_scene_select( mut cmds: Commands )
+{   spawn_menu_text( &mut cmds, Vec::new() );
+}
+
+//シーンの選択画面のキー操作
+//  [↑]/[↓]:選択 [1]～[9]:番号で選択 [Enter]:表示 [Esc]:タイトルへ戻る
+pub fn scene_select_input
+(   inkey: Res<Input<KeyCode>>,
+    mut list: ResMut<SceneList>,
+    mut next_state: ResMut<NextState<AppState>>,
+)
+{   let len = list.scenes.len();
+    if len == 0 { return }
+
+    if inkey.just_pressed( KeyCode::Up   ) { list.cursor = ( list.cursor + len - 1 ) % len }
+    if inkey.just_pressed( KeyCode::Down ) { list.cursor = ( list.cursor + 1 ) % len }
+
+    //数字キーは選んですぐ表示する
+    let digits =
+    [   KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5,
+        KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    ];
+    let digit = digits.iter().position( | key | inkey.just_pressed( *key ) );
+    if let Some ( index ) = digit.filter( | index | *index < len )
+    {   list.cursor = index;
+        next_state.set( AppState::Viewer );
+    }
+
+    let is_alt = inkey.any_pressed( [ KeyCode::AltLeft, KeyCode::AltRight ] );
+    if inkey.just_pressed( KeyCode::Return ) && ! is_alt { next_state.set( AppState::Viewer ) }
+    if inkey.just_pressed( KeyCode::Escape ) { next_state.set( AppState::Title ) }
+}
+
+//シーンの一覧を表示する(選択中のシーンは色を変える)
+pub fn show_scene_select
+(   mut q_menu: Query<&mut Text, With<MenuText>>,
+    list: Res<SceneList>,
+)
+{   let Ok ( mut text ) = q_menu.get_single_mut() else { return };
+
+    let mut sections = vec![ TextSection::new( "Select a scene\n\n", menu_style( Color::GOLD ) ) ];
+    for ( i, scene ) in list.scenes.iter().enumerate()
+    {   let ( mark, color ) = if i == list.cursor { ( ">", Color::YELLOW ) } else { ( " ", Color::WHITE ) };
+        let value = format!( "{mark} {}. {}\n", i + 1, scene.name() );
+        sections.push( TextSection::new( value, menu_style( color ) ) );
+    }
+    sections.push( TextSection::new( "\n[Enter]:view [Esc]:back", menu_style( Color::GRAY ) ) );
+
+    text.sections = sections;
+}
+
+//------------------------------------------------------------------------------
+
+//ビューアのキー操作
+//  [Esc]:シーンの選択へ戻る [P]:一時停止
+pub fn viewer_input
+(   inkey: Res<Input<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+)
+{   if inkey.just_pressed( KeyCode::Escape ) { next_state.set( AppState::SceneSelect ) }
+    if inkey.just_pressed( KeyCode::P ) { next_state.set( AppState::Paused ) }
+}
+
+//一時停止の画面を作る(コマ送り中もシーンが見えるようにウィンドウの上端に出す)
+pub fn spawn_paused
+(   q_window: Query<&Window>,
+    mut cmds: Commands,
+)
+{   let Ok( window ) = q_window.get_single() else { return };
+    let sections = vec!
+    [   TextSection::new( "PAUSED\n", menu_style( Color::YELLOW ) ),
+        TextSection::new( "[P]/[Esc]:resume [N]:step", menu_style( Color::WHITE ) ),
+    ];
+    let text = Text::from_sections( sections ).with_alignment( TextAlignment::Center );
+    let transform = Transform::from_xyz( 0.0, window.height() / 2.0, 0.0 );
+    let text_anchor = Anchor::TopCenter;
+    cmds.spawn( ( Text2dBundle { text, transform, text_anchor, ..default() }, MenuText ) );
+}
+
+//一時停止中のキー操作
+//  [P]/[Esc]:ビューアへ戻る
+pub fn paused_input
+(   inkey: Res<Input<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+)
+{   if inkey.any_just_pressed( [ KeyCode::P, KeyCode::Escape ] ) { next_state.set( AppState::Viewer ) }
+}
@@ -0,0 +1,80 @@
+use super::*;
+
+//------------------------------------------------------------------------------
+
+//極座標カメラの注目点に追いかけさせるエンティティに付けるComponent
+//(ユーザーは追従中も注目点の周りを回転・ズームできる)
+#[derive( Component, Reflect, Clone, Copy )]
+#[reflect( Component )]
+pub struct CameraTarget
+{   pub offset   : Vec3, //エンティティの位置から注目点までのずれ
+    pub smoothing: f32,  //追従の遅れ(秒)。0.0ならぴったり追従する
+}
+
+impl Default for CameraTarget
+{   fn default() -> Self
+    {   Self { offset: Vec3::ZERO, smoothing: CAMERA_TARGET_SMOOTHING }
+    }
+}
+
+//[F]キーで追従できるエンティティに付けるマーカー
+#[derive( Component, Reflect )]
+#[reflect( Component )]
+pub struct Followable;
+
+//------------------------------------------------------------------------------
+
+//[F]キーで追従のON/OFFを切り替える
+//(追従するのは、追従できるエンティティのうち注目点に一番近いものだけ)
+pub fn toggle_follow
+(   q_followable: Query<( Entity, &GlobalTransform, Option<&CameraTarget> ), With<Followable>>,
+    q_camera: Query<&OrbitCamera>,
+    mut cmds: Commands,
+    inkey: Res<Input<KeyCode>>,
+)
+{   if ! inkey.just_pressed( KeyCode::F ) { return }
+
+    //追従中なら止める
+    let mut is_following = false;
+    for ( entity, _, target ) in q_followable.iter()
+    {   if target.is_some()
+        {   cmds.entity( entity ).remove::<CameraTarget>();
+            is_following = true;
+        }
+    }
+    if is_following { return }
+
+    let Ok ( camera ) = q_camera.get_single() else { return };
+    let nearest = q_followable.iter()
+        .map( | ( entity, transform, _ ) | ( entity, transform.translation().distance_squared( camera.focus ) ) )
+        .min_by( | a, b | a.1.total_cmp( &b.1 ) );
+    if let Some ( ( entity, _ ) ) = nearest
+    {   cmds.entity( entity ).insert( CameraTarget::default() );
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//注目点をCameraTargetの付いたエンティティへ近づける
+pub fn follow_camera_target
+(   mut q_camera: Query<&mut OrbitCamera>,
+    q_target: Query<( Entity, &GlobalTransform, &CameraTarget )>,
+    time: Res<Time>,
+)
+{   let Ok ( mut camera ) = q_camera.get_single_mut() else { return };
+    //複数あれば(セーブデータの読込などで)、最初に作ったエンティティを追う
+    let Some ( ( _, transform, target ) ) = q_target.iter().min_by_key( | ( entity, _, _ ) | *entity ) else { return };
+
+    let goal = transform.translation() + target.offset;
+
+    //遅れがないならぴったり追従する
+    if target.smoothing <= 0.0
+    {   camera.focus = goal;
+        return;
+    }
+
+    //指数関数的に近づける(フレームレートに依存しない)
+    let time_delta = time.raw_delta_seconds(); //前回の実行からの経過時間(一時停止中も動かす)
+    let t = 1.0 - ( -time_delta / target.smoothing ).exp();
+    camera.focus = camera.focus.lerp( goal, t );
+}
@@ -0,0 +1,131 @@
+use super::*;
+
+//------------------------------------------------------------------------------
+
+//視点の履歴(ドラッグ・ズーム・キー操作が落ち着いた時点の極座標を記録する)
+#[derive( Resource, Default )]
+pub struct CameraHistory
+{   entries   : Vec<Orbit>,        //記録した視点
+    cursor    : usize,             //現在の視点が履歴のどこにあるか
+    last_orbit: Option<Orbit>,     //前フレームの視点
+    still_secs: f32,               //視点が止まってからの経過時間
+    is_moving : bool,              //視点が動いている最中か
+    tween     : Option<OrbitTween>,//undo/redoのアニメーション
+}
+
+//undo/redoのアニメーションの状態
+struct OrbitTween
+{   from   : Orbit, //開始時の視点
+    to     : Orbit, //目標の視点
+    last   : Orbit, //前フレームで設定した視点(ユーザー操作の割り込み検出用)
+    elapsed: f32,   //経過時間
+}
+
+impl CameraHistory
+{   //履歴のcursorを移動して、その視点へのアニメーションを開始する
+    fn jump( &mut self, cursor: usize, current: Orbit )
+    {   self.cursor = cursor;
+        let to = self.entries[ cursor ];
+        self.tween = Some ( OrbitTween { from: current, to, last: current, elapsed: 0.0 } );
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//[Alt]+[←]/[→]または[Ctrl]+[Z]/[Y]で視点履歴を戻る・進む
+pub fn step_history
+(   mut q_camera: Query<&mut OrbitCamera>,
+    mut history: ResMut<CameraHistory>,
+    time: Res<Time>,
+    inkey: Res<Input<KeyCode>>,
+)
+{   let Ok ( mut camera ) = q_camera.get_single_mut() else { return };
+
+    //キー入力
+    let is_alt  = inkey.any_pressed( [ KeyCode::AltLeft, KeyCode::AltRight ] );
+    let is_ctrl = inkey.any_pressed( [ KeyCode::ControlLeft, KeyCode::ControlRight ] );
+    let is_undo = is_alt  && inkey.just_pressed( KeyCode::Left )
+               || is_ctrl && inkey.just_pressed( KeyCode::Z );
+    let is_redo = is_alt  && inkey.just_pressed( KeyCode::Right )
+               || is_ctrl && inkey.just_pressed( KeyCode::Y );
+
+    //戻る・進む
+    let cursor = history.cursor;
+    if is_undo && cursor > 0
+    {   history.jump( cursor - 1, camera.orbit );
+    }
+    else if is_redo && cursor + 1 < history.entries.len()
+    {   history.jump( cursor + 1, camera.orbit );
+    }
+
+    //アニメーション中でないなら
+    let Some ( tween ) = history.tween.as_mut() else { return };
+
+    //ユーザー操作で視点が動かされたらアニメーションを打ち切る
+    if camera.orbit != tween.last
+    {   history.tween = None;
+        return;
+    }
+
+    //視点を補間する
+    tween.elapsed += time.raw_delta_seconds(); //一時停止中も動かす
+    let t = tween.elapsed / CAMERA_HISTORY_TWEEN_SECS;
+    camera.orbit = tween.from.lerp( tween.to, ease_in_out( t ) );
+    tween.last = camera.orbit;
+
+    //アニメーションが終わったら、目標の視点を前フレームの視点とする(再記録しない)
+    if t >= 1.0
+    {   camera.orbit = tween.to;
+        history.last_orbit = Some ( camera.orbit );
+        history.is_moving = false;
+        history.tween = None;
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//視点の動きが落ち着いたら履歴に記録する
+pub fn record_settled_orbit
+(   q_camera: Query<&OrbitCamera>,
+    mut history: ResMut<CameraHistory>,
+    time: Res<Time>,
+    mouse_button: Res<Input<MouseButton>>,
+)
+{   let Ok ( camera ) = q_camera.get_single() else { return };
+    let orbit = camera.orbit;
+
+    //undo/redoのアニメーション中は記録しない
+    if history.tween.is_some() { return }
+
+    //最初の視点
+    if history.entries.is_empty()
+    {   history.entries.push( orbit );
+        history.last_orbit = Some ( orbit );
+        return;
+    }
+
+    //視点が動いているなら
+    if history.last_orbit != Some ( orbit )
+    {   history.last_orbit = Some ( orbit );
+        history.is_moving = true;
+        history.still_secs = 0.0;
+        return;
+    }
+
+    //止まってから一定時間が経つまで待つ(ドラッグ中も待つ)
+    if ! history.is_moving { return }
+    history.still_secs += time.raw_delta_seconds(); //一時停止中も数える
+    if history.still_secs < CAMERA_HISTORY_SETTLE_SECS { return }
+    if mouse_button.pressed( MouseButton::Left ) { return }
+    history.is_moving = false;
+
+    //現在の視点と同じなら記録しない
+    let cursor = history.cursor;
+    if history.entries[ cursor ] == orbit { return }
+
+    //redoできる履歴を捨ててから記録する
+    history.entries.truncate( cursor + 1 );
+    history.entries.push( orbit );
+    if history.entries.len() > CAMERA_HISTORY_MAX_LEN { history.entries.remove( 0 ); }
+    history.cursor = history.entries.len() - 1;
+}
@@ -0,0 +1,456 @@
+use super::*;
+
+//------------------------------------------------------------------------------
+
+//補間の方法
+#[derive( Clone, Copy, PartialEq, Default, Serialize, Deserialize )]
+pub enum Interpolation
+{   #[default]
+    CatmullRom, //すべてのキーフレームを通る
+    Bezier,     //キーフレームを制御点とする(両端以外は通らない分なめらか)
+}
+
+//キーフレーム
+#[derive( Clone, Copy, Serialize, Deserialize )]
+pub struct Keyframe
+{   pub time : f32,          //時刻(秒)
+    pub orbit: Orbit,        //注目点から見たカメラの位置
+    #[serde( default )]
+    pub focus: Option<Vec3>, //注目点(Noneなら前のキーフレームの注目点を引き継ぐ)
+}
+
+//カメラパス
+#[derive( Resource, Default, Serialize, Deserialize )]
+pub struct CameraPath
+{   #[serde( default )]
+    pub interpolation: Interpolation,
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl CameraPath
+{   //先頭と末尾のキーフレームの時刻
+    fn span( &self ) -> ( f32, f32 )
+    {   let start = self.keyframes.first().map_or( 0.0, | key | key.time );
+        let end   = self.keyframes.last() .map_or( 0.0, | key | key.time );
+        ( start, end )
+    }
+
+    //補間用に( r, Θ, φ )と注目点の組へ変換する(φは回り込みを展開する)
+    fn points( &self ) -> Vec<( Vec3, Vec3 )>
+    {   let mut focus = self.keyframes.iter()
+            .find_map( | key | key.focus )
+            .unwrap_or( Vec3::ZERO );
+        let mut phi = self.keyframes.first().map_or( 0.0, | key | key.orbit.phi );
+
+        self.keyframes.iter().map
+        (   | key |
+            {   let mut phi_delta = ( key.orbit.phi - phi ) % TAU;
+                phi_delta -= if phi_delta >   PI { TAU } else { 0.0 };
+                phi_delta += if phi_delta <= -PI { TAU } else { 0.0 };
+                phi += phi_delta;
+                focus = key.focus.unwrap_or( focus );
+
+                ( Vec3::new( key.orbit.r, key.orbit.theta, phi ), focus )
+            }
+        )
+        .collect()
+    }
+
+    //時刻に対応するカメラの位置と注目点を求める
+    pub fn sample( &self, time: f32 ) -> Option<( Orbit, Option<Vec3> )>
+    {   let keys = &self.keyframes;
+        if keys.is_empty() { return None }
+
+        let points = self.points();
+        let ( start, end ) = self.span();
+        let time = time.clamp( start, end );
+
+        let ( rtp, focus ) = match self.interpolation
+        {   Interpolation::CatmullRom =>
+            {   //時刻を含む区間を探す
+                let last = keys.len() - 1;
+                let i = keys.windows( 2 )
+                    .position( | pair | time <= pair[ 1 ].time )
+                    .unwrap_or( 0 );
+                let j = ( i + 1 ).min( last );
+                let duration = keys[ j ].time - keys[ i ].time;
+                let t = if duration > 0.0 { ( time - keys[ i ].time ) / duration } else { 0.0 };
+
+                //区間の前後の点(両端は端点を繰り返す)
+                let p0 = points[ i.saturating_sub( 1 ) ];
+                let p1 = points[ i ];
+                let p2 = points[ j ];
+                let p3 = points[ ( j + 1 ).min( last ) ];
+                (   catmull_rom( p0.0, p1.0, p2.0, p3.0, t ),
+                    catmull_rom( p0.1, p1.1, p2.1, p3.1, t ),
+                )
+            }
+            Interpolation::Bezier =>
+            {   let duration = end - start;
+                let t = if duration > 0.0 { ( time - start ) / duration } else { 0.0 };
+                (   de_casteljau( points.iter().map( | p | p.0 ).collect(), t ),
+                    de_casteljau( points.iter().map( | p | p.1 ).collect(), t ),
+                )
+            }
+        };
+
+        //補間で行き過ぎた値は極座標カメラの範囲へ収める(rの範囲はカメラごとなので再生側で収める)
+        let orbit = Orbit
+        {   r    : rtp.x,
+            theta: rtp.y.clamp( ORBIT_CAMERA_MIN_THETA, ORBIT_CAMERA_MAX_THETA ),
+            phi  : rtp.z.rem_euclid( TAU ),
+        };
+        let has_focus = keys.iter().any( | key | key.focus.is_some() );
+
+        Some ( ( orbit, has_focus.then_some( focus ) ) )
+    }
+}
+
+//Catmull-Romスプライン
+fn catmull_rom( p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32 ) -> Vec3
+{   let t2 = t * t;
+    let t3 = t2 * t;
+    (   p1 * 2.0
+        + ( p2 - p0 ) * t
+        + ( p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3 ) * t2
+        + ( p1 * 3.0 - p0 - p2 * 3.0 + p3 ) * t3
+    ) * 0.5
+}
+
+//ベジェ曲線(de Casteljauのアルゴリズム)
+fn de_casteljau( mut points: Vec<Vec3>, t: f32 ) -> Vec3
+{   while points.len() > 1
+    {   points = points.windows( 2 ).map( | pair | pair[ 0 ].lerp( pair[ 1 ], t ) ).collect();
+    }
+    points[ 0 ]
+}
+
+//------------------------------------------------------------------------------
+
+//再生モード
+#[derive( Clone, Copy, PartialEq, Default )]
+pub enum PlaybackMode
+{   #[default]
+    Once,     //最後で止まる
+    Loop,     //先頭へ戻って繰り返す
+    PingPong, //往復を繰り返す
+}
+
+//カメラパスの再生状態
+#[derive( Resource, Default )]
+pub struct PathPlayer
+{   is_playing: bool,
+    is_reverse: bool, //PingPongの復路
+    mode      : PlaybackMode,
+    time      : f32,
+}
+
+impl PathPlayer
+{   //HUD用の状態表示
+    pub fn status( &self, path: &CameraPath ) -> String
+    {   let ( _, end ) = path.span();
+        let state = if self.is_playing { "play" } else { "pause" };
+        let mode = match self.mode
+        {   PlaybackMode::Once     => "once",
+            PlaybackMode::Loop     => "loop",
+            PlaybackMode::PingPong => "ping-pong",
+        };
+        let interpolation = match path.interpolation
+        {   Interpolation::CatmullRom => "catmull-rom",
+            Interpolation::Bezier     => "bezier",
+        };
+        format!
+        (   " path:{} keys {state} {:.1}/{end:.1}s\n {mode} {interpolation}",
+            path.keyframes.len(),
+            self.time,
+        )
+    }
+
+    //再生モードに合わせて時刻を先頭～末尾の範囲内へ収める
+    fn wrap_time( &mut self, start: f32, end: f32 )
+    {   let duration = end - start;
+        match self.mode
+        {   PlaybackMode::Once =>
+            {   if self.time >= end { self.is_playing = false }
+                self.time = self.time.clamp( start, end );
+            }
+            PlaybackMode::Loop if duration > 0.0 =>
+                self.time = start + ( self.time - start ).rem_euclid( duration ),
+            PlaybackMode::PingPong if duration > 0.0 =>
+            {   if self.time > end
+                {   self.time = end * 2.0 - self.time;
+                    self.is_reverse = true;
+                }
+                if self.time < start
+                {   self.time = start * 2.0 - self.time;
+                    self.is_reverse = false;
+                }
+                self.time = self.time.clamp( start, end );
+            }
+            _ => self.time = start,
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//キー入力でカメラパスを記録・保存・再生する
+//  [K]:キーフレームを追加 [BackSpace]:全消去 [F2]:補間の切換
+//  [F3]:再生/一時停止 [F4]:再生モードの切換 [F6]:保存 [F7]:読込
+pub fn edit_camera_path
+(   q_camera: Query<&OrbitCamera>,
+    mut path: ResMut<CameraPath>,
+    mut player: ResMut<PathPlayer>,
+    inkey: Res<Input<KeyCode>>,
+)
+{   let Ok ( camera ) = q_camera.get_single() else { return };
+
+    //今のカメラの姿勢をキーフレームとして追加する
+    if inkey.just_pressed( KeyCode::K )
+    {   let time = path.keyframes.last().map_or( 0.0, | key | key.time + CAMERA_PATH_KEY_INTERVAL );
+        let key = Keyframe { time, orbit: camera.orbit, focus: Some ( camera.focus ) };
+        path.keyframes.push( key );
+    }
+
+    //全消去
+    if inkey.just_pressed( KeyCode::Back )
+    {   path.keyframes.clear();
+        *player = PathPlayer { mode: player.mode, ..default() };
+    }
+
+    //補間の切換
+    if inkey.just_pressed( KeyCode::F2 )
+    {   path.interpolation = match path.interpolation
+        {   Interpolation::CatmullRom => Interpolation::Bezier,
+            Interpolation::Bezier     => Interpolation::CatmullRom,
+        };
+    }
+
+    //再生/一時停止(最後まで再生し終わっていたら先頭から)
+    if inkey.just_pressed( KeyCode::F3 ) && ! path.keyframes.is_empty()
+    {   player.is_playing = ! player.is_playing;
+        let ( start, end ) = path.span();
+        if player.is_playing && player.mode == PlaybackMode::Once && player.time >= end
+        {   player.time = start;
+        }
+    }
+
+    //再生モードの切換
+    if inkey.just_pressed( KeyCode::F4 )
+    {   player.mode = match player.mode
+        {   PlaybackMode::Once     => PlaybackMode::Loop,
+            PlaybackMode::Loop     => PlaybackMode::PingPong,
+            PlaybackMode::PingPong => PlaybackMode::Once,
+        };
+        player.is_reverse = false;
+    }
+
+    //保存
+    if inkey.just_pressed( KeyCode::F6 )
+    {   let file = ron_file::asset_path( CAMERA_PATH_FILE );
+        match ron_file::save( &file, &*path )
+        {   Ok  ( () ) => info!( "camera path saved: {}", file.display() ),
+            Err ( err ) => error!( "failed to save camera path: {err}" ),
+        }
+    }
+
+    //読込
+    if inkey.just_pressed( KeyCode::F7 )
+    {   let file = ron_file::asset_path( CAMERA_PATH_FILE );
+        match ron_file::load::<CameraPath>( &file )
+        {   Ok ( mut loaded ) =>
+            {   loaded.keyframes.sort_by( | a, b | a.time.total_cmp( &b.time ) );
+                *path = loaded;
+                *player = PathPlayer { mode: player.mode, ..default() };
+                player.time = path.span().0;
+                info!( "camera path loaded: {}", file.display() );
+            }
+            Err ( err ) => error!( "failed to load camera path: {err}" ),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//カメラパスを再生して極座標カメラを動かす([,]/[.]でスクラブ)
+pub fn play_camera_path
+(   mut q_camera: Query<&mut OrbitCamera>,
+    path: Res<CameraPath>,
+    mut player: ResMut<PathPlayer>,
+    time: Res<Time>,
+    inkey: Res<Input<KeyCode>>,
+)
+{   let Ok ( mut camera ) = q_camera.get_single_mut() else { return };
+
+    //スクラブの方向
+    let mut scrub = 0.0;
+    scrub += if inkey.pressed( KeyCode::Period ) { 1.0 } else { 0.0 };
+    scrub -= if inkey.pressed( KeyCode::Comma  ) { 1.0 } else { 0.0 };
+
+    //再生もスクラブもしていないならカメラは自由に動かせる
+    if ! player.is_playing && scrub == 0.0 { return }
+    if path.keyframes.is_empty()
+    {   player.is_playing = false;
+        return;
+    }
+
+    //時刻を進める
+    let time_delta = time.delta_seconds(); //前回の実行からの経過時間(一時停止・速度変更の影響を受ける)
+    if player.is_playing
+    {   player.time += if player.is_reverse { -time_delta } else { time_delta };
+    }
+    player.time += scrub * time.raw_delta_seconds() * CAMERA_PATH_SCRUB_SPEED; //スクラブは一時停止中もできる
+
+    //再生モードに合わせて時刻を範囲内へ収める
+    let ( start, end ) = path.span();
+    player.wrap_time( start, end );
+
+    //カメラを動かす
+    let Some ( ( orbit, focus ) ) = path.sample( player.time ) else { return };
+    camera.orbit = Orbit { r: orbit.r.clamp( camera.min_r, camera.max_r ), ..orbit };
+    if let Some ( focus ) = focus { camera.focus = focus }
+}
+
+//------------------------------------------------------------------------------
+
+#[cfg( test )]
+mod tests
+{   use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn key( time: f32, r: f32, theta: f32, phi: f32 ) -> Keyframe
+    {   Keyframe { time, orbit: Orbit { r, theta, phi }, focus: None }
+    }
+
+    fn path( interpolation: Interpolation, keyframes: Vec<Keyframe> ) -> CameraPath
+    {   CameraPath { interpolation, keyframes }
+    }
+
+    fn assert_orbit( actual: Orbit, expected: Orbit )
+    {   let phi_gap = ( actual.phi - expected.phi ).rem_euclid( TAU );
+        assert!( ( actual.r - expected.r ).abs() < EPSILON, "r {} != {}", actual.r, expected.r );
+        assert!( ( actual.theta - expected.theta ).abs() < EPSILON, "theta {} != {}", actual.theta, expected.theta );
+        assert!( phi_gap.min( TAU - phi_gap ) < EPSILON, "phi {} != {}", actual.phi, expected.phi );
+    }
+
+    //キーフレームが無ければNone、1つならどの時刻でもそのキーフレーム
+    #[test]
+    fn sample_empty_and_single_key()
+    {   for interpolation in [ Interpolation::CatmullRom, Interpolation::Bezier ]
+        {   assert!( path( interpolation, vec![] ).sample( 0.0 ).is_none() );
+
+            let mut only = key( 1.0, 3.0, 2.0, 0.5 );
+            only.focus = Some ( Vec3::new( 1.0, 2.0, 3.0 ) );
+            let path = path( interpolation, vec![ only ] );
+            for time in [ -1.0, 1.0, 5.0 ]
+            {   let ( orbit, focus ) = path.sample( time ).unwrap();
+                assert_orbit( orbit, only.orbit );
+                assert_eq!( focus, only.focus );
+            }
+        }
+    }
+
+    //2つのキーフレームでは両端を通り、中間は中点になる。範囲外の時刻は両端に収める
+    #[test]
+    fn sample_two_keys_and_clamp_ends()
+    {   let a = key( 0.0, 2.0, 2.0, 0.0 );
+        let b = key( 2.0, 4.0, 3.0, 1.0 );
+        for interpolation in [ Interpolation::CatmullRom, Interpolation::Bezier ]
+        {   let path = path( interpolation, vec![ a, b ] );
+            assert_orbit( path.sample( -1.0 ).unwrap().0, a.orbit );
+            assert_orbit( path.sample(  0.0 ).unwrap().0, a.orbit );
+            assert_orbit( path.sample(  1.0 ).unwrap().0, Orbit { r: 3.0, theta: 2.5, phi: 0.5 } );
+            assert_orbit( path.sample(  2.0 ).unwrap().0, b.orbit );
+            assert_orbit( path.sample(  9.0 ).unwrap().0, b.orbit );
+            assert!( path.sample( 1.0 ).unwrap().1.is_none() ); //注目点の無いパス
+        }
+    }
+
+    //Catmull-Romは途中のキーフレームも通り、ベジェは制御点として扱う
+    #[test]
+    fn sample_three_keys()
+    {   let keys = vec![ key( 0.0, 2.0, 2.0, 0.0 ), key( 1.0, 4.0, 2.5, 1.0 ), key( 2.0, 2.0, 2.0, 2.0 ) ];
+
+        let through = path( Interpolation::CatmullRom, keys.clone() );
+        assert_orbit( through.sample( 1.0 ).unwrap().0, keys[ 1 ].orbit );
+
+        //( 1 - t )^2, 2t( 1 - t ), t^2 の重み(t = 0.5)
+        let control = path( Interpolation::Bezier, keys );
+        assert_orbit( control.sample( 1.0 ).unwrap().0, Orbit { r: 3.0, theta: 2.25, phi: 1.0 } );
+    }
+
+    //φは±πをまたぐ近い方へ補間する
+    #[test]
+    fn sample_unwraps_phi()
+    {   for interpolation in [ Interpolation::CatmullRom, Interpolation::Bezier ]
+        {   let path = path( interpolation, vec![ key( 0.0, 2.0, 2.0, TAU - 0.2 ), key( 1.0, 2.0, 2.0, 0.2 ) ] );
+            assert_orbit( path.sample( 0.5 ).unwrap().0, Orbit { r: 2.0, theta: 2.0, phi: 0.0 } );
+
+            //遠回りせず、ずっと0の近くを通る
+            for i in 0..=10
+            {   let phi = path.sample( i as f32 * 0.1 ).unwrap().0.phi;
+                assert!( phi.min( TAU - phi ) <= 0.2 + EPSILON, "phi {phi}" );
+            }
+        }
+    }
+
+    //補間で行き過ぎたΘは極座標カメラの範囲へ収める
+    #[test]
+    fn sample_clamps_theta()
+    {   let keys = vec![ key( 0.0, 2.0, ORBIT_CAMERA_MAX_THETA, 0.0 ), key( 1.0, 2.0, ORBIT_CAMERA_MAX_THETA, 0.0 ), key( 2.0, 2.0, ORBIT_CAMERA_MIN_THETA, 0.0 ) ];
+        let path = path( Interpolation::CatmullRom, keys );
+        for i in 0..=20
+        {   let theta = path.sample( i as f32 * 0.1 ).unwrap().0.theta;
+            assert!( ( ORBIT_CAMERA_MIN_THETA..=ORBIT_CAMERA_MAX_THETA ).contains( &theta ), "theta {theta}" );
+        }
+    }
+
+    //注目点は前のキーフレームの注目点を引き継ぐ
+    #[test]
+    fn sample_carries_focus()
+    {   let mut a = key( 0.0, 2.0, 2.0, 0.0 );
+        a.focus = Some ( Vec3::X );
+        let b = key( 1.0, 2.0, 2.0, 0.0 );
+        let path = path( Interpolation::CatmullRom, vec![ a, b ] );
+        let focus = path.sample( 0.5 ).unwrap().1.unwrap();
+        assert!( focus.distance( Vec3::X ) < EPSILON );
+    }
+
+    fn player( mode: PlaybackMode, time: f32, is_reverse: bool ) -> PathPlayer
+    {   PathPlayer { is_playing: true, is_reverse, mode, time }
+    }
+
+    //再生モードごとの時刻の収め方
+    #[test]
+    fn wrap_time_by_mode()
+    {   //最後で止まる
+        let mut once = player( PlaybackMode::Once, 3.5, false );
+        once.wrap_time( 1.0, 3.0 );
+        assert_eq!( ( once.time, once.is_playing ), ( 3.0, false ) );
+
+        //先頭へ戻る
+        let mut looped = player( PlaybackMode::Loop, 3.5, false );
+        looped.wrap_time( 1.0, 3.0 );
+        assert!( ( looped.time - 1.5 ).abs() < EPSILON );
+        assert!( looped.is_playing );
+
+        //末尾で折り返して復路へ、先頭で折り返して往路へ
+        let mut ping_pong = player( PlaybackMode::PingPong, 3.5, false );
+        ping_pong.wrap_time( 1.0, 3.0 );
+        assert!( ( ping_pong.time - 2.5 ).abs() < EPSILON );
+        assert!( ping_pong.is_reverse );
+
+        let mut ping_pong = player( PlaybackMode::PingPong, 0.75, true );
+        ping_pong.wrap_time( 1.0, 3.0 );
+        assert!( ( ping_pong.time - 1.25 ).abs() < EPSILON );
+        assert!( ! ping_pong.is_reverse );
+
+        //長さ0のパスは先頭に留まる
+        for mode in [ PlaybackMode::Loop, PlaybackMode::PingPong ]
+        {   let mut player = player( mode, 4.0, false );
+            player.wrap_time( 2.0, 2.0 );
+            assert_eq!( player.time, 2.0 );
+        }
+    }
+}
@@ -2,74 +2,212 @@ use super::*;
 
 //------------------------------------------------------------------------------
 
+//ズームのモード
+#[derive( Resource, Reflect, Clone, Copy, PartialEq, Default )]
+#[reflect( Resource )]
+pub enum ZoomMode
+{   #[default]
+    Distance,  //極座標のrを変える
+    Fov,       //視野角を変える(望遠レンズ風)
+    DollyZoom, //被写体の見かけの大きさを保ったままrと視野角を一緒に変える
+}
+
+impl ZoomMode
+{   //HUD用の名前
+    pub fn name( self ) -> &'static str
+    {   match self
+        {   Self::Distance  => "distance",
+            Self::Fov       => "fov",
+            Self::DollyZoom => "dolly zoom",
+        }
+    }
+}
+
+//ズームする(amountは極座標のrの増分に相当する量)
+fn zoom( camera: &mut OrbitCamera, projection: &mut Projection, mode: ZoomMode, amount: f32 )
+{   let Projection::Perspective ( perspective ) = projection else { return };
+    let ( min_r, max_r ) = ( camera.min_r, camera.max_r );
+    let orbit = &mut camera.orbit;
+
+    match mode
+    {   ZoomMode::Distance =>
+            orbit.r = ( orbit.r + amount ).clamp( min_r, max_r ),
+        ZoomMode::Fov =>
+            perspective.fov = ( perspective.fov + amount * CAMERA_FOV_PER_R )
+                .clamp( CAMERA_MIN_FOV, CAMERA_MAX_FOV ),
+        ZoomMode::DollyZoom =>
+        {   //被写体の見かけの大きさ r×tan(fov/2) を保つ
+            let size = orbit.r * ( perspective.fov * 0.5 ).tan();
+            let r = ( orbit.r + amount ).clamp( min_r, max_r );
+            let fov = ( ( size / r ).atan() * 2.0 ).clamp( CAMERA_MIN_FOV, CAMERA_MAX_FOV );
+
+            //視野角が範囲の端に当たったらrの方を合わせる
+            orbit.r = ( size / ( fov * 0.5 ).tan() ).clamp( min_r, max_r );
+            perspective.fov = fov;
+        }
+    }
+}
+
+//[V]キーでズームのモードを切り替える
+pub fn toggle_zoom_mode
+(   mut zoom_mode: ResMut<ZoomMode>,
+    inkey: Res<Input<KeyCode>>,
+)
+{   if ! inkey.just_pressed( KeyCode::V ) { return }
+
+    *zoom_mode = match *zoom_mode
+    {   ZoomMode::Distance  => ZoomMode::Fov,
+        ZoomMode::Fov       => ZoomMode::DollyZoom,
+        ZoomMode::DollyZoom => ZoomMode::Distance,
+    };
+}
+
+//------------------------------------------------------------------------------
+
+//左ドラッグで極座標を回す
+fn rotate_orbit( orbit: &mut Orbit, delta: Vec2 )
+{   //上下首振り
+    orbit.theta += delta.y * MOUSE_MOTION_Y_COEF; //感度良すぎるので
+    orbit.theta = orbit.theta
+        .min( ORBIT_CAMERA_MAX_THETA )
+        .max( ORBIT_CAMERA_MIN_THETA );
+
+    //左右回転
+    orbit.phi -= delta.x * MOUSE_MOTION_X_COEF; //感度良すぎるので
+    orbit.phi -= if orbit.phi >= TAU { TAU } else { 0.0 };
+    orbit.phi += if orbit.phi <  0.0 { TAU } else { 0.0 };
+}
+
 //マウス入力によって極座標カメラの位置を更新する
+//([L]を押しながらの左ドラッグは、カメラの代わりに平行光源の向きを変える)
 pub fn from_mouse
-(   mut q_camera: Query<&mut OrbitCamera>,
+(   mut q_camera: Query<( &mut OrbitCamera, &mut Projection )>,
+    mut q_lights: Query<( Entity, &mut light_rig::OrbitLight )>,
+    rig: Res<light_rig::LightRig>,
+    zoom_mode: Res<ZoomMode>,
+    inkey: Res<Input<KeyCode>>,
     mouse_nutton: Res<Input<MouseButton>>,
     mut e_mouse_motion: EventReader<MouseMotion>,
     mut e_mouse_wheel: EventReader<MouseWheel>,
 )
-{   let Ok ( mut camera ) = q_camera.get_single_mut() else { return };
-    let orbit = &mut camera.orbit;
+{   let Ok ( ( mut camera, mut projection ) ) = q_camera.get_single_mut() else { return };
 
     //ホイール
     for mouse_wheel in e_mouse_wheel.iter()
-    {   orbit.r += mouse_wheel.y * MOUSE_WHEEL_Y_COEF; //感度良すぎるので
-        orbit.r = orbit.r
-            .min( ORBIT_CAMERA_MAX_R )
-            .max( ORBIT_CAMERA_MIN_R );
+    {   let amount = mouse_wheel.y * MOUSE_WHEEL_Y_COEF; //感度良すぎるので
+        zoom( &mut camera, &mut projection, *zoom_mode, amount );
     }
 
-    //右ボタンが押されていないなら
-    if ! mouse_nutton.pressed( MouseButton::Left ) { return }
-
-    //マウスの上下左右
-    for mouse_motion in e_mouse_motion.iter()
-    {   //上下首振り
-        orbit.theta += mouse_motion.delta.y * MOUSE_MOTION_Y_COEF; //感度良すぎるので
-        orbit.theta = orbit.theta
-            .min( ORBIT_CAMERA_MAX_THETA )
-            .max( ORBIT_CAMERA_MIN_THETA );
-
-        //左右回転
-        orbit.phi -= mouse_motion.delta.x * MOUSE_MOTION_X_COEF; //感度良すぎるので
-        orbit.phi -= if orbit.phi >= TAU { TAU } else { 0.0 };
-        orbit.phi += if orbit.phi <  0.0 { TAU } else { 0.0 };
+    //マウスの上下左右(ボタンが押されていなくても読み捨てる)
+    let delta = e_mouse_motion.iter().map( | mouse_motion | mouse_motion.delta ).sum::<Vec2>();
+
+    //[L]+左ドラッグ(光源を回す。動かさない時は変更を検出させない)
+    if mouse_nutton.pressed( MouseButton::Left ) && inkey.pressed( KeyCode::L )
+    {   let target = light_rig::orbit_light_target( &rig, q_lights.iter().map( | ( entity, _ ) | entity ) );
+        if let Some ( ( _, mut light ) ) = target.and_then( | entity | q_lights.get_mut( entity ).ok() )
+        {   if delta != Vec2::ZERO { rotate_orbit( &mut light.orbit, delta ); }
+        }
+    }
+    //左ドラッグ
+    else if mouse_nutton.pressed( MouseButton::Left )
+    {   rotate_orbit( &mut camera.orbit, delta );
+    }
+    //右ドラッグ(注目点を水平に動かす。地面をつかんで引っ張る向き)
+    else if mouse_nutton.pressed( MouseButton::Right )
+    {   let ( sin, cos ) = camera.orbit.phi.sin_cos();
+        let forward = Vec3::new( -sin, 0.0, -cos ); //カメラの向き(水平成分)
+        let right   = Vec3::new(  cos, 0.0, -sin ); //カメラの右(水平成分)
+        let scale = camera.orbit.r * MOUSE_PAN_COEF; //遠くから見ている時は大きく動かす
+        camera.focus += ( forward * delta.y - right * delta.x ) * scale;
     }
 }
 
 //------------------------------------------------------------------------------
 
+//キー操作の設定値(軸ごとの最高速度や加速の具合)
+#[derive( Resource, Reflect, Clone, Copy )]
+#[reflect( Resource )]
+pub struct KeyboardSettings
+{   pub max_speed   : Vec3, //最高速度( r/秒, Θラジアン/秒, φラジアン/秒 )
+    pub start_ratio : f32,  //押し始めの速度(最高速度に対する比率)
+    pub accel_secs  : f32,  //最高速度に達するまでの時間(秒)
+    pub fast_coef   : f32,  //[Shift]で速くする倍率
+    pub precise_coef: f32,  //[Ctrl]で遅くする倍率
+}
+
+impl Default for KeyboardSettings
+{   fn default() -> Self
+    {   Self
+        {   max_speed   : KEYBOARD_MAX_SPEED,
+            start_ratio : KEYBOARD_START_RATIO,
+            accel_secs  : KEYBOARD_ACCEL_SECS,
+            fast_coef   : KEYBOARD_FAST_COEF,
+            precise_coef: KEYBOARD_PRECISE_COEF,
+        }
+    }
+}
+
 //キー入力によって極座標カメラの位置を更新する
+//  [Z]/[X]:ズーム [↑]/[↓]または[W]/[S]:上下 [←]/[→]または[A]/[D]:左右
+//  [Shift]:速く [Ctrl]:ゆっくり(押し続けると最高速度まで加速する)
 pub fn from_keyboard
-(   mut q_camera: Query<&mut OrbitCamera>,
+(   mut q_camera: Query<( &mut OrbitCamera, &mut Projection )>,
+    zoom_mode: Res<ZoomMode>,
+    settings: Res<KeyboardSettings>,
     time: Res<Time>,
     inkey: Res<Input<KeyCode>>,
+    mut ratio: Local<Vec3>, //各軸の最高速度に対する割合(向きの符号付き、ローカル変数)
 )
-{   let Ok ( mut camera ) = q_camera.get_single_mut() else { return };
-    let orbit = &mut camera.orbit;
+{   let Ok ( ( mut camera, mut projection ) ) = q_camera.get_single_mut() else { return };
 
-    let time_delta = time.delta().as_secs_f32(); //前回の実行からの経過時間
+    //[Alt]との同時押しは視点履歴のundo/redoなので無視する
+    let is_alt   = inkey.any_pressed( [ KeyCode::AltLeft, KeyCode::AltRight ] );
+    let is_ctrl  = inkey.any_pressed( [ KeyCode::ControlLeft, KeyCode::ControlRight ] );
+    let is_shift = inkey.any_pressed( [ KeyCode::ShiftLeft, KeyCode::ShiftRight ] );
+    if is_alt { *ratio = Vec3::ZERO; return }
 
+    //押されているキーから各軸の向きを求める( x:r, y:Θ, z:φ )
+    let mut direction = Vec3::ZERO;
     for keycode in inkey.get_pressed()
     {   match keycode
-        {   KeyCode::Z =>
-                orbit.r = ( orbit.r + time_delta ).min( ORBIT_CAMERA_MAX_R ),
-            KeyCode::X =>
-                orbit.r = ( orbit.r - time_delta ).max( ORBIT_CAMERA_MIN_R ),
-            KeyCode::Up =>
-                orbit.theta = ( orbit.theta + time_delta ).min( ORBIT_CAMERA_MAX_THETA ),
-            KeyCode::Down =>
-                orbit.theta = ( orbit.theta - time_delta ).max( ORBIT_CAMERA_MIN_THETA ),
-            KeyCode::Right =>
-            {   orbit.phi -= time_delta;
-                orbit.phi += if orbit.phi < 0.0 { TAU } else { 0.0 };
-            }
-            KeyCode::Left =>
-            {   orbit.phi += time_delta;
-                orbit.phi -= if orbit.phi >= TAU { TAU } else { 0.0 };
-            }
+        {   KeyCode::Z if ! is_ctrl => direction.x += 1.0, //[Ctrl]+[Z]はundo
+            KeyCode::X              => direction.x -= 1.0,
+            KeyCode::Up    | KeyCode::W => direction.y += 1.0,
+            KeyCode::Down  | KeyCode::S => direction.y -= 1.0,
+            KeyCode::Left  | KeyCode::A => direction.z += 1.0,
+            KeyCode::Right | KeyCode::D => direction.z -= 1.0,
             _ => (),
         }
     }
-}
\ No newline at end of file
+
+    //同時押しでも速くならないように正規化する
+    direction = direction.clamp( Vec3::NEG_ONE, Vec3::ONE );
+    if direction.length_squared() > 1.0 { direction = direction.normalize() }
+
+    //目標の速度(修飾キーで増減する)
+    let mut coef = 1.0;
+    coef *= if is_shift { settings.fast_coef    } else { 1.0 };
+    coef *= if is_ctrl  { settings.precise_coef } else { 1.0 };
+    let max_speed = settings.max_speed * coef;
+
+    //押されていない軸は止め、押されている軸は最高速度まで加速する
+    //(割合は速度から逆算せずに覚えておく。斜めの同時押しで正規化した分だけ加速が鈍らないように)
+    let time_delta = time.raw_delta().as_secs_f32(); //前回の実行からの経過時間(一時停止中も動かす)
+    let accel = time_delta / settings.accel_secs.max( f32::EPSILON );
+    for axis in 0..3
+    {   let is_same_way = ratio[ axis ] * direction[ axis ] > 0.0;
+        ratio[ axis ] = if direction[ axis ] == 0.0 { 0.0 } else
+        {   let next = if is_same_way { ratio[ axis ].abs() + accel } else { settings.start_ratio };
+            next.clamp( settings.start_ratio, 1.0 ).copysign( direction[ axis ] )
+        };
+    }
+    if *ratio == Vec3::ZERO { return }
+    let velocity = direction * max_speed * ratio.abs();
+
+    //極座標を更新する
+    let delta = velocity * time_delta;
+    if delta.x != 0.0 { zoom( &mut camera, &mut projection, *zoom_mode, delta.x ) }
+    let orbit = &mut camera.orbit;
+    orbit.theta = ( orbit.theta + delta.y ).clamp( ORBIT_CAMERA_MIN_THETA, ORBIT_CAMERA_MAX_THETA );
+    orbit.phi   = ( orbit.phi   + delta.z ).rem_euclid( TAU );
+}
@@ -0,0 +1,179 @@
+use super::*;
+
+use scene_file::MaterialDesc;
+use material_library::MaterialLibrary;
+
+//------------------------------------------------------------------------------
+
+//宝箱の寸法・マテリアル
+#[derive( Clone, Deserialize )]
+#[serde( default, deny_unknown_fields )]
+pub struct ChestSpec
+{   pub width      : f32, //本体の幅(X)
+    pub depth      : f32, //本体の奥行(Z)
+    pub body_height: f32, //本体の高さ(Y)
+    pub lid_radius : f32, //上蓋(円柱)の半径
+    pub lock_size  : f32, //錠前(立方体)の一辺
+    pub lock_offset: f32, //本体の中心から錠前の中心までの距離(Z)
+    pub body_material   : MaterialDesc,
+    pub lid_material    : MaterialDesc,
+    pub lock_material   : MaterialDesc,
+    pub keyhole_material: MaterialDesc,
+    pub locked: bool,     //錠前と鍵穴を付けるか(鍵がかかった状態になる)
+    pub key_id: String,   //錠前を開ける鍵のID
+    pub loot_table: Option<String>, //中身のドロップテーブルの名前(なければ空の宝箱)
+}
+
+//手で調整した元の宝箱の寸法
+impl Default for ChestSpec
+{   fn default() -> Self
+    {   Self
+        {   width      : 0.7,
+            depth      : 0.4,
+            body_height: 0.3,
+            lid_radius : 0.195,
+            lock_size  : 0.1,
+            lock_offset: 0.17,
+            body_material   : MaterialDesc::Named( "wood".to_string() ),
+            lid_material    : MaterialDesc::Named( "wood".to_string() ),
+            lock_material   : MaterialDesc::Named( "iron".to_string() ),
+            keyhole_material: MaterialDesc::Rgb( 0.0, 0.0, 0.0 ), //Color::BLACK
+            locked: true,
+            key_id: "chest_key".to_string(),
+            loot_table: Some ( "chest".to_string() ),
+        }
+    }
+}
+
+impl ChestSpec
+{   //寸法とマテリアルをチェックする
+    pub fn validate( &self ) -> Result<(), String>
+    {   let sizes = [ self.width, self.depth, self.body_height, self.lid_radius, self.lock_size ];
+        if sizes.iter().any( | size | ! size.is_finite() || *size <= 0.0 )
+        {   return Err ( "chest sizes must be positive numbers".to_string() );
+        }
+        if self.width <= CHEST_LID_GAP //上蓋の円柱の高さが0以下になる
+        {   return Err ( format!( "chest width must be greater than {CHEST_LID_GAP}" ) );
+        }
+        if ! self.lock_offset.is_finite()
+        {   return Err ( "chest lock_offset must be a finite number".to_string() );
+        }
+        if self.locked && self.key_id.is_empty()
+        {   return Err ( "locked chest needs a key_id".to_string() );
+        }
+
+        let materials = [ &self.body_material, &self.lid_material, &self.lock_material, &self.keyhole_material ];
+        for material in materials { material.validate()?; }
+
+        Ok (())
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//宝箱のルートに付けるComponent
+#[derive( Component, Reflect )]
+#[reflect( Component )]
+pub struct TreasureChest;
+
+//宝箱の部品に付けるマーカー
+#[derive( Component, Reflect )] #[reflect( Component )] pub struct ChestBody;
+#[derive( Component, Reflect )] #[reflect( Component )] pub struct ChestLid;
+#[derive( Component, Reflect )] #[reflect( Component )] pub struct ChestLock;
+#[derive( Component, Reflect )] #[reflect( Component )] pub struct ChestKeyhole;
+
+//------------------------------------------------------------------------------
+
+//Commandsに宝箱を作るメソッドを追加する
+pub trait SpawnTreasureChest
+{   fn spawn_treasure_chest( &mut self, spec: ChestSpec, transform: Transform ) -> Option<Entity>;
+}
+
+impl SpawnTreasureChest for Commands<'_, '_>
+{   //宝箱を作る(transformは本体の底面の中心の位置。寸法などがおかしければログに出して作らない)
+    fn spawn_treasure_chest( &mut self, spec: ChestSpec, transform: Transform ) -> Option<Entity>
+    {   if let Err ( message ) = spec.validate()
+        {   error!( "failed to spawn treasure chest: {message}" );
+            return None
+        }
+
+        let root = self.spawn( SpatialBundle::from_transform( transform ) )
+            .insert( ( TreasureChest, Name::new( "chest" ) ) )
+            .id();
+        if spec.locked { self.entity( root ).insert( lock_key::Locked { key_id: spec.key_id.clone() } ); }
+        if let Some ( table ) = &spec.loot_table { self.entity( root ).insert( loot::Loot { table: table.clone() } ); }
+
+        //メッシュとマテリアルを作るためにWorldへ直接アクセスする
+        self.add( move | world: &mut World | build_chest( world, root, &spec ) );
+
+        Some ( root )
+    }
+}
+
+//宝箱の部品を作ってルートの子にする
+fn build_chest( world: &mut World, root: Entity, spec: &ChestSpec )
+{   //ルートがすでに消されていたら何もしない
+    if world.get_entity( root ).is_none() { return }
+
+    //メッシュ
+    let mut meshes = world.resource_mut::<Assets<Mesh>>();
+    let body_mesh = meshes.add( shape::Box::new( spec.width, spec.body_height, spec.depth ).into() );
+    let lid_mesh = meshes.add
+    (   shape::Cylinder
+        {   radius: spec.lid_radius,
+            height: spec.width - CHEST_LID_GAP, //本体と面が重ならないよう少し短くする
+            ..default()
+        }
+        .into()
+    );
+    let lock_mesh = meshes.add( shape::Cube::new( spec.lock_size ).into() );
+    let keyhole_size = spec.lock_size * CHEST_KEYHOLE_RATIO;
+    let keyhole_mesh = meshes.add( shape::Box::new( keyhole_size.x, keyhole_size.y, keyhole_size.z ).into() );
+
+    //マテリアル(ライブラリで共有する)
+    let [ body_material, lid_material, lock_material, keyhole_material ] =
+        world.resource_scope( | world, mut library: Mut<MaterialLibrary> |
+            world.resource_scope( | world, mut images: Mut<Assets<Image>> |
+            {   let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+                [ &spec.body_material, &spec.lid_material, &spec.lock_material, &spec.keyhole_material ]
+                    .map( | desc | library.handle( desc, &mut materials, &mut images ) )
+            })
+        );
+
+    //本体
+    let transform = Transform::from_xyz( 0.0, spec.body_height * 0.5, 0.0 );
+    let body = world.spawn( PbrBundle { mesh: body_mesh, material: body_material, transform, ..default() } )
+        .insert( ( ChestBody, Name::new( "body" ) ) )
+        .id();
+
+    //上蓋の蝶番(本体の上面の奥の辺)
+    let transform = Transform::from_xyz( 0.0, spec.body_height, spec.depth * -0.5 );
+    let hinge = world.spawn( SpatialBundle::from_transform( transform ) )
+        .insert( ( chest_lid::LidHinge::default(), Name::new( "lid_hinge" ) ) )
+        .id();
+
+    //上蓋(円柱を横に倒して、蝶番から本体の上面の中心へずらす)
+    let transform = Transform::from_xyz( 0.0, 0.0, spec.depth * 0.5 )
+        .with_rotation( Quat::from_rotation_z( PI * 0.5 ) );
+    let lid = world.spawn( PbrBundle { mesh: lid_mesh, material: lid_material, transform, ..default() } )
+        .insert( ( ChestLid, Name::new( "lid" ) ) )
+        .id();
+
+    world.entity_mut( hinge ).push_children( &[ lid ] );
+    world.entity_mut( root ).push_children( &[ body, hinge ] );
+    if ! spec.locked { return }
+
+    //錠前
+    let transform = Transform::from_xyz( 0.0, spec.body_height, spec.lock_offset );
+    let lock = world.spawn( PbrBundle { mesh: lock_mesh, material: lock_material, transform, ..default() } )
+        .insert( ( ChestLock, Name::new( "lock" ) ) )
+        .id();
+
+    //鍵穴(錠前の子)
+    let keyhole = world.spawn( PbrBundle { mesh: keyhole_mesh, material: keyhole_material, ..default() } )
+        .insert( ( ChestKeyhole, Name::new( "keyhole" ) ) )
+        .id();
+
+    world.entity_mut( lock ).push_children( &[ keyhole ] );
+    world.entity_mut( root ).push_children( &[ lock ] );
+}
@@ -0,0 +1,117 @@
+use super::*;
+
+use chest::{ TreasureChest, ChestLock };
+use lock_key::Locked;
+use hud_message::HudMessage;
+use picking::MeshClicked;
+
+//------------------------------------------------------------------------------
+
+//上蓋の蝶番に付けるComponent(宝箱のルートの子で、上蓋はこの子になる)
+#[derive( Component, Reflect, Default )]
+#[reflect( Component )]
+pub struct LidHinge
+{   pub is_open : bool, //開く方向へ動いているか(開いているか)
+    pub progress: f32,  //0.0:閉じている ～ 1.0:開いている
+}
+
+//上蓋の開閉を切り替えるイベント
+#[derive( Event, Clone, Copy )]
+pub struct ToggleLid { pub chest: Entity }
+
+//上蓋が開き終わった・閉じ終わった時のイベント
+#[derive( Event, Clone, Copy )]
+pub struct LidOpened { pub chest: Entity }
+#[derive( Event, Clone, Copy )]
+pub struct LidClosed { pub chest: Entity }
+
+//------------------------------------------------------------------------------
+
+//[Space]キーで注目点に一番近い宝箱の上蓋を開閉する
+pub fn toggle_lid_by_key
+(   q_camera: Query<&OrbitCamera>,
+    q_chest: Query<( Entity, &GlobalTransform ), With<TreasureChest>>,
+    inkey: Res<Input<KeyCode>>,
+    mut e_toggle: EventWriter<ToggleLid>,
+)
+{   if ! inkey.just_pressed( KeyCode::Space ) { return }
+    let Ok ( camera ) = q_camera.get_single() else { return };
+
+    let nearest = q_chest.iter()
+        .map( | ( entity, transform ) | ( entity, transform.translation().distance( camera.focus ) ) )
+        .min_by( | a, b | a.1.total_cmp( &b.1 ) );
+    let Some ( ( chest, _ ) ) = nearest else { return };
+
+    e_toggle.send( ToggleLid { chest } );
+}
+
+//クリックされた宝箱の上蓋を開閉する
+pub fn toggle_lid_by_click
+(   q_chest: Query<(), With<TreasureChest>>,
+    q_lock: Query<(), With<ChestLock>>,
+    q_parent: Query<&Parent>,
+    mut e_clicked: EventReader<MeshClicked>,
+    mut e_toggle: EventWriter<ToggleLid>,
+)
+{   for clicked in e_clicked.iter()
+    {   //[Shift]+クリックは部品の選択なので無視する
+        if clicked.is_shift { continue }
+
+        //錠前のクリックは鍵を開ける操作なので無視する
+        let is_lock = | entity | q_lock.contains( entity );
+        if picking::find_ancestor( clicked.entity, &q_parent, is_lock ).is_some() { continue }
+
+        let is_chest = | entity | q_chest.contains( entity );
+        let Some ( chest ) = picking::find_ancestor( clicked.entity, &q_parent, is_chest ) else { continue };
+        e_toggle.send( ToggleLid { chest } );
+    }
+}
+
+//イベントを受けて上蓋の動く方向を切り替える(鍵がかかっていたら開けない)
+pub fn apply_toggle_lid
+(   mut q_hinge: Query<( &Parent, &mut LidHinge )>,
+    q_locked: Query<(), With<Locked>>,
+    mut e_toggle: EventReader<ToggleLid>,
+    mut message: ResMut<HudMessage>,
+    time: Res<Time>,
+)
+{   for toggle in e_toggle.iter()
+    {   if q_locked.contains( toggle.chest )
+        {   message.show( "The chest is locked", &time );
+            continue
+        }
+
+        for ( _, mut hinge ) in q_hinge.iter_mut().filter( | ( parent, _ ) | parent.get() == toggle.chest )
+        {   hinge.is_open = ! hinge.is_open;
+        }
+    }
+}
+
+//上蓋を動かして、開き終わり・閉じ終わりにイベントを送る
+pub fn animate_lids
+(   mut q_hinge: Query<( &Parent, &mut LidHinge, &mut Transform )>,
+    time: Res<Time>,
+    mut e_opened: EventWriter<LidOpened>,
+    mut e_closed: EventWriter<LidClosed>,
+)
+{   let time_delta = time.delta_seconds(); //前回の実行からの経過時間
+    let step = time_delta / CHEST_LID_SECS;
+
+    for ( parent, mut hinge, mut transform ) in q_hinge.iter_mut()
+    {   let target = if hinge.is_open { 1.0 } else { 0.0 };
+        if hinge.progress == target { continue }
+
+        //目標へ近づける
+        let progress = if hinge.is_open { hinge.progress + step } else { hinge.progress - step };
+        hinge.progress = progress.clamp( 0.0, 1.0 );
+
+        //蝶番をX軸まわりに回す(手前が持ち上がる向き)
+        let angle = CHEST_LID_OPEN_ANGLE * ease_in_out( hinge.progress );
+        transform.rotation = Quat::from_rotation_x( -angle );
+
+        //動き終わったらイベントを送る
+        if hinge.progress != target { continue }
+        let chest = parent.get();
+        if hinge.is_open { e_opened.send( LidOpened { chest } ) } else { e_closed.send( LidClosed { chest } ) }
+    }
+}
@@ -11,6 +11,132 @@ pub const CAMERA2D_BGCOLOR: ClearColorConfig = ClearColorConfig::None;
 pub const LIGHT_BRIGHTNESS: f32 = 15000.0; //明るさ
 pub const LIGHT_POSITION: Vec3 = Vec3::new( 30.0, 100.0, 40.0 ); //位置
 
+//光源リグ
+pub const LIGHT_RIG_FILE        : &str = "light_rig.ron";            //保存先(assetsフォルダからの相対パス)
+pub const LIGHT_UP_THRESHOLD    : f32  = 0.99;                       //向きがY軸とこれ以上平行ならup軸をZ軸にする
+pub const LIGHT_RANGE           : f32  = 20.0;                       //点光源・スポットライトの届く距離
+pub const LIGHT_SPOT_OUTER_ANGLE: f32  = 30.0;                       //スポットライトの外側の角度(度)
+pub const LIGHT_SPOT_INNER_ANGLE: f32  = 20.0;                       //スポットライトの内側の角度(度)
+pub const LIGHT_MIN_CASCADE     : f32  = 0.1;                        //影のカスケードの最小距離
+pub const LIGHT_FIRST_CASCADE   : f32  = 5.0;                        //最初のカスケードの距離
+pub const LIGHT_SHADOW_DISTANCE : f32  = 100.0;                      //影が落ちる最大距離
+pub const LIGHT_POINT_INTENSITY : f32  = 800.0;                      //追加した点光源・スポットライトの強さ(lm)
+pub const LIGHT_POINT_OFFSET    : Vec3 = Vec3::new( 0.0, 2.0, 0.0 ); //追加した点光源の注目点からの位置
+pub const LIGHT_SPOT_OFFSET     : Vec3 = Vec3::new( 1.5, 2.5, 1.5 ); //追加したスポットライトの注目点からの位置
+pub const LIGHT_INTENSITY_STEP  : f32  = 1.25;                       //[Home]/[End]で強さを変える倍率
+
+//[C]で切り替える光の色
+pub const LIGHT_COLORS: [ Color; 6 ] =
+[   Color::WHITE,
+    Color::rgb( 1.0, 0.85, 0.6 ), //暖色
+    Color::rgb( 0.7, 0.8, 1.0 ),  //寒色
+    Color::rgb( 1.0, 0.4, 0.4 ),
+    Color::rgb( 0.4, 1.0, 0.4 ),
+    Color::rgb( 0.4, 0.4, 1.0 ),
+];
+
+//昼夜の移り変わり
+pub const DAY_NIGHT_INIT_HOUR    : f32 = 10.0;        //有効にした時の時刻
+pub const DAY_NIGHT_HOURS_PER_SEC: f32 = 0.5;         //1秒あたりに進む時間(初期値)
+pub const DAY_NIGHT_MIN_SPEED    : f32 = 1.0 / 64.0;  //1秒あたりに進む時間の下限
+pub const DAY_NIGHT_MAX_SPEED    : f32 = 16.0;        //1秒あたりに進む時間の上限
+pub const DAY_NIGHT_SUN_TILT     : f32 = PI / 6.0;    //正午の太陽の天頂からの傾き(ラジアン)
+
+//時刻ごとの太陽・環境光・空の色(時刻順。間は線形補間し、24時の次は0時に戻る)
+pub const DAY_NIGHT_KEYS: [ day_night::SkyKey; 8 ] =
+[   day_night::SkyKey //真夜中
+    {   hour: 0.0,
+        sun_color: Color::rgb( 0.5, 0.6, 1.0 ), illuminance: 0.0,
+        ambient_color: Color::rgb( 0.3, 0.35, 0.6 ), ambient_brightness: 0.02,
+        sky_color: Color::rgb( 0.01, 0.01, 0.04 ),
+    },
+    day_night::SkyKey //夜明け前
+    {   hour: 5.0,
+        sun_color: Color::rgb( 0.8, 0.5, 0.4 ), illuminance: 0.0,
+        ambient_color: Color::rgb( 0.4, 0.35, 0.6 ), ambient_brightness: 0.03,
+        sky_color: Color::rgb( 0.12, 0.08, 0.2 ),
+    },
+    day_night::SkyKey //日の出
+    {   hour: 6.0,
+        sun_color: Color::rgb( 1.0, 0.55, 0.3 ), illuminance: 2000.0,
+        ambient_color: Color::rgb( 0.9, 0.6, 0.5 ), ambient_brightness: 0.06,
+        sky_color: Color::rgb( 0.9, 0.5, 0.35 ),
+    },
+    day_night::SkyKey //朝
+    {   hour: 8.0,
+        sun_color: Color::rgb( 1.0, 0.9, 0.8 ), illuminance: 10000.0,
+        ambient_color: Color::rgb( 0.9, 0.9, 1.0 ), ambient_brightness: 0.1,
+        sky_color: Color::rgb( 0.45, 0.65, 0.9 ),
+    },
+    day_night::SkyKey //正午
+    {   hour: 12.0,
+        sun_color: Color::WHITE, illuminance: LIGHT_BRIGHTNESS,
+        ambient_color: Color::WHITE, ambient_brightness: 0.15,
+        sky_color: Color::rgb( 0.4, 0.65, 1.0 ),
+    },
+    day_night::SkyKey //夕方
+    {   hour: 16.0,
+        sun_color: Color::rgb( 1.0, 0.9, 0.8 ), illuminance: 10000.0,
+        ambient_color: Color::rgb( 0.9, 0.9, 1.0 ), ambient_brightness: 0.1,
+        sky_color: Color::rgb( 0.45, 0.65, 0.9 ),
+    },
+    day_night::SkyKey //日の入り
+    {   hour: 18.0,
+        sun_color: Color::rgb( 1.0, 0.45, 0.2 ), illuminance: 2000.0,
+        ambient_color: Color::rgb( 0.9, 0.55, 0.5 ), ambient_brightness: 0.06,
+        sky_color: Color::rgb( 0.95, 0.45, 0.3 ),
+    },
+    day_night::SkyKey //宵
+    {   hour: 19.0,
+        sun_color: Color::rgb( 0.8, 0.4, 0.4 ), illuminance: 0.0,
+        ambient_color: Color::rgb( 0.35, 0.3, 0.6 ), ambient_brightness: 0.03,
+        sky_color: Color::rgb( 0.15, 0.08, 0.25 ),
+    },
+];
+
+//スカイボックス
+pub const SKYBOX_FILE                  : &str  = "skybox.ron";                  //設定ファイル(assetsフォルダからの相対パス)
+pub const SKYBOX_GRADIENT_SIZE         : u32   = 64;                            //グラデーションの空の一面のピクセル数
+pub const SKYBOX_EQUIRECT_MAX_FACE_SIZE: u32   = 1024;                          //正距円筒図法の画像から作る一面のピクセル数の上限
+pub const SKYBOX_GROUND_BLEND          : f32   = 4.0;                           //地平線から地面の色に変わる速さ
+pub const SKYBOX_ZENITH_COLOR          : Color = Color::rgb( 0.2, 0.45, 0.9 );  //天頂の色
+pub const SKYBOX_HORIZON_COLOR         : Color = Color::rgb( 0.75, 0.85, 1.0 ); //地平線の色
+pub const SKYBOX_GROUND_COLOR          : Color = Color::rgb( 0.3, 0.28, 0.25 ); //地面の色
+
+//地面の目盛り([G]で表示)
+pub const GRID_SPACING_EXPONENT: f32   = -1.0;                             //目盛りの間隔はrの桁にこれを足した10の累乗
+pub const GRID_MAJOR_EVERY     : u32   = 10;                               //大きな目盛りの間隔(目盛りの数)
+pub const GRID_HALF_CELLS      : u32   = 20;                               //中心から端までの目盛りの数
+pub const GRID_Y_OFFSET        : f32   = 0.001;                            //地面のメッシュと重ならないように浮かせる高さ
+pub const GRID_MAJOR_COLOR     : Color = Color::rgba( 0.8, 0.8, 0.8, 0.8 ); //大きな目盛りの色
+pub const GRID_MINOR_COLOR     : Color = Color::rgba( 0.6, 0.6, 0.6, 0.4 ); //細かい目盛りの色
+
+//デバッグ表示([Ctrl]+[1]～[6]で切換)
+pub const DEBUG_CIRCLE_SEGMENTS   : usize = 48;                                    //円の分割数
+pub const DEBUG_CONE_LINES        : usize = 12;                                    //Θの制限の円錐の母線の数
+pub const DEBUG_FOCUS_SIZE        : f32   = 0.05;                                  //注目点の十字の大きさ(rに対する比率)
+pub const DEBUG_TRAIL_SECS        : f32   = 3.0;                                   //カメラの軌跡を残す時間(秒)
+pub const DEBUG_TRAIL_MIN_STEP    : f32   = 0.01;                                  //カメラの軌跡を記録する最小の移動量
+pub const DEBUG_LIGHT_ARROW_LENGTH: f32   = 0.5;                                   //平行光源の矢印の長さ(rに対する比率)
+pub const DEBUG_ARROW_HEAD_SIZE   : f32   = 0.15;                                  //矢じりの大きさ(矢印の長さに対する比率)
+pub const DEBUG_ORBIT_SPHERE_COLOR: Color = Color::rgba( 0.3, 0.8, 1.0, 0.3 );
+pub const DEBUG_MIN_THETA_COLOR   : Color = Color::ORANGE;
+pub const DEBUG_MAX_THETA_COLOR   : Color = Color::FUCHSIA;
+pub const DEBUG_FOCUS_COLOR       : Color = Color::WHITE;
+pub const DEBUG_TRAIL_COLOR       : Color = Color::CYAN;
+pub const DEBUG_LIGHT_COLOR       : Color = Color::YELLOW;
+pub const DEBUG_AABB_COLOR        : Color = Color::LIME_GREEN;
+pub const DEBUG_CASCADE_COLORS    : [ Color; 4 ] = [ Color::RED, Color::GREEN, Color::BLUE, Color::PURPLE ]; //カスケードごとの色
+
+//表示モード([R]で切換)
+pub const RENDER_MODE_NORMAL_LENGTH: f32   = 0.02;          //頂点法線の線の長さ(rに対する比率)
+pub const RENDER_MODE_MAX_NORMALS  : usize = 20000;         //頂点法線の線の上限
+pub const RENDER_MODE_NORMAL_COLOR : Color = Color::FUCHSIA; //頂点法線の線の色
+
+//部品の表示の切換
+pub const PART_XRAY_ALPHA    : f32   = 0.3;           //半透明にした部品の不透明度(元の不透明度に掛ける)
+pub const PART_SELECTED_COLOR: Color = Color::ORANGE; //選んだ部品の枠の色
+
 //UIテキスト
 pub const UI_TEXT_FONT_SIZE: f32 = 50.0;
 
@@ -19,17 +145,108 @@ pub const ORBIT_CAMERA_INIT_R    : f32 = 3.0;      //初期値
 pub const ORBIT_CAMERA_INIT_THETA: f32 = PI * 0.7; //初期値(ラジアン)
 pub const ORBIT_CAMERA_INIT_PHI  : f32 = 0.0;      //初期値(ラジアン)
 
-pub const ORBIT_CAMERA_MAX_R    : f32 = 5.0;       //最大値
-pub const ORBIT_CAMERA_MIN_R    : f32 = 1.0;       //最小値
+pub const ORBIT_CAMERA_MAX_R    : f32 = 5.0;       //最大値(初期値)
+pub const ORBIT_CAMERA_MIN_R    : f32 = 1.0;       //最小値(初期値)
 pub const ORBIT_CAMERA_MAX_THETA: f32 = PI * 0.99; //最大値(ラジアン)
 pub const ORBIT_CAMERA_MIN_THETA: f32 = PI * 0.51; //最小値(ラジアン)
 
+//視野角の設定値(ラジアン)
+pub const CAMERA_INIT_FOV : f32 = PI / 4.0;           //初期値(Bevyのデフォルトと同じ)
+pub const CAMERA_MAX_FOV  : f32 = PI / 2.0;           //最大値
+pub const CAMERA_MIN_FOV  : f32 = PI / 36.0;          //最小値(望遠)
+pub const CAMERA_FOV_PER_R: f32 = 0.2;                //ズーム量(rの増分)を視野角へ換算する係数
+
+//glTFモデルに合わせたrの設定値(画面にちょうど収まる距離に対する倍率。最小値だけは半径に対する倍率)
+pub const MODEL_INIT_R_COEF: f32 = 1.1;
+pub const MODEL_MAX_R_COEF : f32 = 4.0;
+pub const MODEL_MIN_R_COEF : f32 = 0.5;
+
+//宝箱の部品の比率
+pub const CHEST_LID_GAP      : f32  = 0.005;                     //上蓋を本体の幅より短くする長さ
+pub const CHEST_KEYHOLE_RATIO: Vec3 = Vec3::new( 0.1, 0.4, 1.1 ); //錠前の一辺に対する鍵穴の大きさ
+
+//宝箱の上蓋の開閉
+pub const CHEST_LID_SECS      : f32 = 0.8;       //開閉にかかる時間(秒)
+pub const CHEST_LID_OPEN_ANGLE: f32 = PI * 0.6;  //開いた時の角度(ラジアン)
+
+//マテリアルライブラリ
+pub const MATERIALS_FILE    : &str = "materials.ron"; //名前付きのマテリアルの定義(assetsフォルダからの相対パス)
+pub const MATERIAL_EDIT_STEP: f32  = 0.05;            //実行中の編集でmetallic・roughnessを変える量
+
+//プロシージャルテクスチャ
+pub const PROCEDURAL_TEXTURE_SCALE   : f32 = 8.0;  //模様の繰り返し数(省略した時)
+pub const PROCEDURAL_TEXTURE_SIZE    : u32 = 256;  //画像の一辺のピクセル数(同上)
+pub const PROCEDURAL_TEXTURE_MAX_SIZE: u32 = 2048; //画像の一辺の最大ピクセル数
+pub const PROCEDURAL_TEXTURE_OCTAVES : u32 = 4;    //重ねるノイズの数
+
+//宝箱の中身とインベントリ
+pub const LOOT_TABLES_FILE   : &str = "loot_tables.ron"; //ドロップテーブル(assetsフォルダからの相対パス)
+pub const INVENTORY_FILE     : &str = "inventory.ron";   //インベントリの保存先(同上)
+pub const INVENTORY_FONT_SIZE: f32  = 24.0;
+
+//シーンの状態の保存先([F5]で保存、[F9]で読込。assetsフォルダからの相対パス)
+pub const SCENE_STATE_FILE: &str = "saves/scene_state.scn.ron";
+
+//宝探しモード
+pub const HUNT_CHEST_COUNT  : usize = 8;     //宝箱の数(起動オプションで数を省略した時)
+pub const HUNT_MAX_CHESTS   : usize = 64;    //起動オプションで指定できる宝箱の数の上限
+pub const HUNT_GROUND_SIZE  : f32   = 12.0;  //地面の一辺
+pub const HUNT_CHEST_GAP    : f32   = 1.2;   //宝箱の中心どうしの最小距離
+pub const HUNT_PLACE_TRIES  : usize = 100;   //宝箱1個あたりの配置の試行回数
+pub const HUNT_FIND_RADIUS  : f32   = 1.0;   //注目点がこの距離まで近づいたら発見
+pub const HUNT_FOUND_SCORE  : u32   = 10;    //発見した時の得点
+pub const HUNT_OPENED_SCORE : u32   = 50;    //開けた時の得点
+pub const HUNT_PAR_SECS     : f32   = 180.0; //この時間より早く終われば残り時間がボーナス
+pub const HUNT_BONUS_PER_SEC: f32   = 5.0;   //早く終わった1秒あたりのボーナス
+
+//タイトル・シーンの選択の画面
+pub const MENU_FONT_SIZE: f32  = 40.0;
+pub const MODELS_DIR    : &str = "models"; //シーンの一覧に載せるglTFファイルのフォルダ(assetsフォルダからの相対パス)
+
+//2Dテキストのデモ(hello, world)
+pub const HELLO_FONT_SIZE  : f32  = 50.0;
+pub const HELLO_MOVE_RADIUS: Vec2 = Vec2::new( 400.0, 200.0 ); //楕円軌道の半径
+
+//時間の速さの段階([-]/[=]で切り替える)
+pub const TIME_SCALES: [ f32; 7 ] = [ 0.125, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0 ];
+
+//HUDのメッセージを表示しておく時間(秒)
+pub const HUD_MESSAGE_SECS: f32 = 3.0;
+
+//クリックとみなすカーソルの移動量(これより動いたらドラッグ)
+pub const CLICK_MAX_MOVE: f32 = 4.0;
+
 //マウスからの入力値の感度調整用係数
 pub const MOUSE_WHEEL_Y_COEF : f32 = 0.1;
 pub const MOUSE_MOTION_Y_COEF: f32 = 0.01;
 pub const MOUSE_MOTION_X_COEF: f32 = 0.01;
+pub const MOUSE_PAN_COEF     : f32 = 0.002; //右ドラッグで注目点を動かす量(rに比例)
+
+//キー操作の設定値
+pub const KEYBOARD_MAX_SPEED   : Vec3 = Vec3::new( 2.0, 1.5, 2.0 ); //最高速度( r, Θ, φ )/秒
+pub const KEYBOARD_START_RATIO : f32  = 0.3; //押し始めの速度(最高速度に対する比率)
+pub const KEYBOARD_ACCEL_SECS  : f32  = 0.8; //最高速度に達するまでの時間(秒)
+pub const KEYBOARD_FAST_COEF   : f32  = 3.0; //[Shift]の倍率
+pub const KEYBOARD_PRECISE_COEF: f32  = 0.2; //[Ctrl]の倍率
 
 //viewportの設定値(表示エリアの矩形)
 pub const VIEWPORT_WIDTH : f32  = 600.0;
 pub const VIEWPORT_HEIGHT: f32  = 600.0;
-pub const VIEWPORT_SIZE  : Vec2 = Vec2::new( VIEWPORT_WIDTH, VIEWPORT_HEIGHT );
\ No newline at end of file
+pub const VIEWPORT_SIZE  : Vec2 = Vec2::new( VIEWPORT_WIDTH, VIEWPORT_HEIGHT );
+
+//視点履歴の設定値
+pub const CAMERA_HISTORY_MAX_LEN    : usize = 50;  //記録する視点の最大数
+pub const CAMERA_HISTORY_SETTLE_SECS: f32   = 0.3; //この時間だけ止まったら視点を記録する
+pub const CAMERA_HISTORY_TWEEN_SECS : f32   = 0.4; //undo/redoのアニメーション時間
+
+//カメラパスの設定値
+pub const CAMERA_PATH_KEY_INTERVAL: f32 = 2.0; //記録したキーフレームの時間間隔(秒)
+pub const CAMERA_PATH_SCRUB_SPEED : f32 = 2.0; //スクラブ操作の速さ(倍速)
+pub const CAMERA_PATH_FILE: &str = "camera_path.ron"; //保存先(assetsフォルダ内)
+
+//assetsフォルダとシーンファイル
+pub const ASSETS_DIR: &str = "assets";
+pub const LOCKED_CHEST_SCENE_FILE: &str = "scenes/locked_chest.ron";
+
+//追従カメラの設定値
+pub const CAMERA_TARGET_SMOOTHING: f32 = 0.2; //追従の遅れ(秒)
\ No newline at end of file
@@ -0,0 +1,172 @@
+use super::*;
+
+use light_rig::OrbitLight;
+
+//------------------------------------------------------------------------------
+
+//ある時刻の太陽・環境光・空の色(DAY_NIGHT_KEYSの1項目)
+#[derive( Clone, Copy )]
+pub struct SkyKey
+{   pub hour              : f32,   //時刻(0.0～24.0)
+    pub sun_color         : Color, //太陽の光の色
+    pub illuminance       : f32,   //太陽の明るさ(lux)
+    pub ambient_color     : Color, //環境光の色
+    pub ambient_brightness: f32,   //環境光の明るさ
+    pub sky_color         : Color, //空の色(ClearColor)
+}
+
+impl SkyKey
+{   //時刻の太陽・環境光・空の色(前後のキーを線形補間する)
+    fn at( hour: f32 ) -> Self
+    {   let keys = &DAY_NIGHT_KEYS;
+        let next = keys.iter().position( | key | key.hour > hour ).unwrap_or( keys.len() );
+        let a = keys[ ( next + keys.len() - 1 ) % keys.len() ];
+        let b = keys[ next % keys.len() ];
+
+        //最後のキーから最初のキーへは24時をまたぐ
+        let span = ( b.hour - a.hour ).rem_euclid( 24.0 );
+        let t = if span > 0.0 { ( hour - a.hour ).rem_euclid( 24.0 ) / span } else { 0.0 };
+        let lerp = | a: f32, b: f32 | a + ( b - a ) * t;
+
+        Self
+        {   hour,
+            sun_color         : mix( a.sun_color, b.sun_color, t ),
+            illuminance       : lerp( a.illuminance, b.illuminance ),
+            ambient_color     : mix( a.ambient_color, b.ambient_color, t ),
+            ambient_brightness: lerp( a.ambient_brightness, b.ambient_brightness ),
+            sky_color         : mix( a.sky_color, b.sky_color, t ),
+        }
+    }
+}
+
+//昼夜の移り変わりを有効にする前の光源と空(無効にした時に戻す)
+struct SavedLighting
+{   sun: Option<( Entity, Orbit, Color, f32 )>, //太陽にした光源と、その極座標・色・明るさ
+    ambient: AmbientLight,
+    clear_color: Color,
+}
+
+//昼夜の移り変わりの状態
+#[derive( Resource, Reflect )]
+#[reflect( Resource )]
+pub struct DayNight
+{   pub is_enabled   : bool,
+    pub hour         : f32, //時刻(0.0～24.0)
+    pub hours_per_sec: f32, //1秒あたりに進む時間
+    #[reflect( ignore )]
+    saved: Option<SavedLighting>,
+}
+
+impl Default for DayNight
+{   fn default() -> Self
+    {   Self
+        {   is_enabled   : false,
+            hour         : DAY_NIGHT_INIT_HOUR,
+            hours_per_sec: DAY_NIGHT_HOURS_PER_SEC,
+            saved        : None,
+        }
+    }
+}
+
+impl DayNight
+{   //保存ファイルを読み込んだ後に止める
+    //(有効にする前の光源は保存されないので、読み込んだ光源と空をそのまま使う)
+    pub fn stop_after_load( &mut self )
+    {   self.is_enabled = false;
+        self.saved = None;
+    }
+
+    //HUDに表示する時計
+    pub fn status( &self ) -> Option<String>
+    {   if ! self.is_enabled { return None }
+        let minutes = ( self.hour * 60.0 ) as u32;
+        Some ( format!
+        (   " clock:{:02}:{:02} speed:{:.2}h/s",
+            minutes / 60 % 24, minutes % 60, self.hours_per_sec,
+        ))
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//時刻の太陽の方向(6時に東(+X)から昇り、正午に天頂から傾いた位置を通って、18時に西へ沈む)
+fn sun_direction( hour: f32 ) -> Vec3
+{   let angle = ( hour - 6.0 ) / 24.0 * TAU;
+    let ( sin, cos ) = angle.sin_cos();
+    Vec3::new( cos, sin * DAY_NIGHT_SUN_TILT.cos(), sin * DAY_NIGHT_SUN_TILT.sin() )
+}
+
+//太陽にする光源(最初に作った極座標の平行光源)
+fn sun_entity( q_sun: &SunQuery ) -> Option<Entity>
+{   q_sun.iter().map( | ( entity, _, _ ) | entity ).min()
+}
+
+//昼夜の移り変わりで使うQuery
+type SunQuery<'w, 's> = Query<'w, 's, ( Entity, &'static mut OrbitLight, &'static mut DirectionalLight )>;
+
+//------------------------------------------------------------------------------
+
+//昼夜の移り変わりの操作
+//  [J]:有効・無効の切換 [8]/[9]:時間の進みを遅く・速く
+pub fn operate_day_night
+(   mut day_night: ResMut<DayNight>,
+    mut q_sun: SunQuery,
+    mut ambient: ResMut<AmbientLight>,
+    mut clear_color: ResMut<ClearColor>,
+    inkey: Res<Input<KeyCode>>,
+)
+{   if inkey.just_pressed( KeyCode::Key8 )
+    {   day_night.hours_per_sec = ( day_night.hours_per_sec * 0.5 ).max( DAY_NIGHT_MIN_SPEED );
+    }
+    if inkey.just_pressed( KeyCode::Key9 )
+    {   day_night.hours_per_sec = ( day_night.hours_per_sec * 2.0 ).min( DAY_NIGHT_MAX_SPEED );
+    }
+    if ! inkey.just_pressed( KeyCode::J ) { return }
+
+    day_night.is_enabled = ! day_night.is_enabled;
+    if day_night.is_enabled
+    {   //無効にした時に戻せるように、今の光源と空を覚えておく
+        let sun = sun_entity( &q_sun )
+            .and_then( | entity | q_sun.get( entity ).ok() )
+            .map( | ( entity, orbit_light, light ) | ( entity, orbit_light.orbit, light.color, light.illuminance ) );
+        day_night.saved = Some ( SavedLighting { sun, ambient: ambient.clone(), clear_color: clear_color.0 } );
+    }
+    else if let Some ( saved ) = day_night.saved.take()
+    {   if let Some ( ( entity, orbit, color, illuminance ) ) = saved.sun
+        {   if let Ok ( ( _, mut orbit_light, mut light ) ) = q_sun.get_mut( entity )
+            {   orbit_light.orbit = orbit;
+                light.color = color;
+                light.illuminance = illuminance;
+            }
+        }
+        *ambient = saved.ambient;
+        clear_color.0 = saved.clear_color;
+    }
+}
+
+//時刻を進めて、太陽の位置と色・環境光・空の色を時刻に合わせる
+//(太陽の向きはlight_rig::move_orbit_lightsで反映される)
+pub fn advance_day_night
+(   mut day_night: ResMut<DayNight>,
+    mut q_sun: SunQuery,
+    mut ambient: ResMut<AmbientLight>,
+    mut clear_color: ResMut<ClearColor>,
+    time: Res<Time>,
+)
+{   if ! day_night.is_enabled { return }
+
+    let time_delta = time.delta_seconds(); //前回の実行からの経過時間(一時停止・速度変更の影響を受ける)
+    day_night.hour = ( day_night.hour + time_delta * day_night.hours_per_sec ).rem_euclid( 24.0 );
+
+    let key = SkyKey::at( day_night.hour );
+    ambient.color = key.ambient_color;
+    ambient.brightness = key.ambient_brightness;
+    clear_color.0 = key.sky_color;
+
+    let sun = sun_entity( &q_sun );
+    let Some ( ( _, mut orbit_light, mut light ) ) = sun.and_then( | entity | q_sun.get_mut( entity ).ok() ) else { return };
+    let r = orbit_light.orbit.r;
+    orbit_light.orbit = Orbit::from_vec3( sun_direction( day_night.hour ) * r );
+    light.color = key.sun_color;
+    light.illuminance = key.illuminance;
+}
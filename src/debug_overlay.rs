@@ -0,0 +1,186 @@
+use super::*;
+
+use std::collections::VecDeque;
+use bevy::{ pbr::CascadeShadowConfig, render::primitives::Aabb };
+use hud_message::HudMessage;
+
+//------------------------------------------------------------------------------
+
+//デバッグ表示の切換の状態
+#[derive( Resource, Default )]
+pub struct DebugOverlays
+{   pub orbit_sphere: bool,            //[Ctrl]+[1]:極座標カメラの球面(今のr)
+    pub theta_limits: bool,            //[Ctrl]+[2]:Θの制限の円錐
+    pub focus       : bool,            //[Ctrl]+[3]:注目点
+    pub trail       : bool,            //[Ctrl]+[4]:カメラの軌跡
+    pub light       : bool,            //[Ctrl]+[5]:平行光源の向きと影の範囲
+    pub aabbs       : bool,            //[Ctrl]+[6]:宝箱の部品のAABB
+    trail_points: VecDeque<( Vec3, f32 )>, //カメラの軌跡(位置と記録した時刻)
+}
+
+//デバッグ表示に使う平行光源のQuery
+type DebugLightQuery<'w, 's> = Query
+<   'w, 's,
+    ( &'static GlobalTransform, &'static DirectionalLight, &'static CascadeShadowConfig )
+>;
+
+//AABBを表示する宝箱の部品のQuery
+type ChestPartQuery<'w, 's> = Query
+<   'w, 's,
+    ( &'static Aabb, &'static GlobalTransform ),
+    Or<( With<chest::ChestBody>, With<chest::ChestLid>, With<chest::ChestLock>, With<chest::ChestKeyhole> )>,
+>;
+
+//------------------------------------------------------------------------------
+
+//[Ctrl]+[1]～[6]でデバッグ表示を切り換える
+pub fn toggle_debug_overlays
+(   mut overlays: ResMut<DebugOverlays>,
+    mut message: ResMut<HudMessage>,
+    inkey: Res<Input<KeyCode>>,
+    time: Res<Time>,
+)
+{   if ! inkey.any_pressed( [ KeyCode::ControlLeft, KeyCode::ControlRight ] ) { return }
+
+    let overlays = &mut *overlays;
+    let toggles =
+    [   ( KeyCode::Key1, &mut overlays.orbit_sphere, "orbit sphere" ),
+        ( KeyCode::Key2, &mut overlays.theta_limits, "theta limits" ),
+        ( KeyCode::Key3, &mut overlays.focus,        "focus"        ),
+        ( KeyCode::Key4, &mut overlays.trail,        "camera trail" ),
+        ( KeyCode::Key5, &mut overlays.light,        "light"        ),
+        ( KeyCode::Key6, &mut overlays.aabbs,        "chest AABBs"  ),
+    ];
+    for ( key, is_shown, name ) in toggles
+    {   if ! inkey.just_pressed( key ) { continue }
+        *is_shown = ! *is_shown;
+        let state = if *is_shown { "on" } else { "off" };
+        message.show( format!( "Overlay {name}: {state}" ), &time );
+    }
+    if ! overlays.trail { overlays.trail_points.clear() }
+}
+
+//デバッグ表示を描く
+pub fn draw_debug_overlays
+(   mut gizmos: Gizmos,
+    mut overlays: ResMut<DebugOverlays>,
+    q_camera: Query<( &OrbitCamera, &Projection, &GlobalTransform )>,
+    q_lights: DebugLightQuery,
+    q_parts: ChestPartQuery,
+    time: Res<Time>,
+)
+{   let Ok ( ( camera, projection, camera_transform ) ) = q_camera.get_single() else { return };
+    let Orbit { r, phi, .. } = camera.orbit;
+    let focus = camera.focus;
+
+    //極座標カメラの球面
+    if overlays.orbit_sphere
+    {   gizmos.sphere( focus, Quat::IDENTITY, r, DEBUG_ORBIT_SPHERE_COLOR ).circle_segments( DEBUG_CIRCLE_SEGMENTS );
+    }
+
+    //Θの制限の円錐(注目点から制限の角度の円へ線を引く)
+    if overlays.theta_limits
+    {   for ( theta, color ) in [ ( ORBIT_CAMERA_MIN_THETA, DEBUG_MIN_THETA_COLOR ), ( ORBIT_CAMERA_MAX_THETA, DEBUG_MAX_THETA_COLOR ) ]
+        {   let center = focus + Vec3::Y * -r * theta.cos();
+            gizmos.circle( center, Vec3::Y, r * theta.sin(), color ).segments( DEBUG_CIRCLE_SEGMENTS );
+            for i in 0..DEBUG_CONE_LINES
+            {   let phi = phi + TAU * i as f32 / DEBUG_CONE_LINES as f32;
+                gizmos.line( focus, focus + Orbit { r, theta, phi }.into_vec3(), color );
+            }
+        }
+    }
+
+    //注目点(rに比例した大きさの十字)
+    if overlays.focus
+    {   let size = r * DEBUG_FOCUS_SIZE;
+        for axis in [ Vec3::X, Vec3::Y, Vec3::Z ]
+        {   gizmos.line( focus - axis * size, focus + axis * size, DEBUG_FOCUS_COLOR );
+        }
+    }
+
+    //カメラの軌跡(古いほど薄くする。一時停止中も記録する)
+    let now = time.raw_elapsed_seconds();
+    if overlays.trail
+    {   let position = focus + camera.orbit.into_vec3();
+        let is_moved = overlays.trail_points.back()
+            .map_or( true, | ( last, _ ) | last.distance( position ) > DEBUG_TRAIL_MIN_STEP );
+        if is_moved { overlays.trail_points.push_back( ( position, now ) ) }
+        while overlays.trail_points.front().is_some_and( | ( _, at ) | now - at > DEBUG_TRAIL_SECS )
+        {   overlays.trail_points.pop_front();
+        }
+
+        let faded = | at: f32 | DEBUG_TRAIL_COLOR.with_a( 1.0 - ( now - at ) / DEBUG_TRAIL_SECS );
+        gizmos.linestrip_gradient( overlays.trail_points.iter().map( | ( position, at ) | ( *position, faded( *at ) ) ) );
+    }
+
+    //平行光源の向き(注目点に向かう矢印)と、カスケードごとの影の範囲
+    if overlays.light
+    {   for ( light_transform, light, config ) in q_lights.iter()
+        {   let direction = light_transform.forward();
+            draw_arrow( &mut gizmos, focus - direction * r * DEBUG_LIGHT_ARROW_LENGTH, focus, DEBUG_LIGHT_COLOR );
+            if light.shadows_enabled
+            {   draw_shadow_cascades( &mut gizmos, camera_transform, projection, light_transform, config );
+            }
+        }
+    }
+
+    //宝箱の部品のAABB
+    if overlays.aabbs
+    {   for ( aabb, transform ) in q_parts.iter()
+        {   let local = Transform::from_translation( aabb.center.into() ).with_scale( ( aabb.half_extents * 2.0 ).into() );
+            gizmos.cuboid( *transform * local, DEBUG_AABB_COLOR );
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//矢印を描く
+fn draw_arrow( gizmos: &mut Gizmos, start: Vec3, end: Vec3, color: Color )
+{   gizmos.line( start, end, color );
+
+    let direction = ( end - start ).normalize_or_zero();
+    let size = start.distance( end ) * DEBUG_ARROW_HEAD_SIZE;
+    let side = direction.any_orthonormal_vector();
+    for normal in [ side, -side, direction.cross( side ), -direction.cross( side ) ]
+    {   gizmos.line( end, end - direction * size + normal * size * 0.5, color );
+    }
+}
+
+//カメラの視錐台をカスケードごとに区切り、平行光源の向きで囲んだ箱を描く
+//(Bevyが影を描く範囲のおおよその形。カスケードの距離の調整に使う)
+fn draw_shadow_cascades
+(   gizmos: &mut Gizmos,
+    camera_transform: &GlobalTransform,
+    projection: &Projection,
+    light_transform: &GlobalTransform,
+    config: &CascadeShadowConfig,
+)
+{   let Projection::Perspective ( perspective ) = projection else { return };
+    let tan_half_fov = ( perspective.fov * 0.5 ).tan();
+    let rotation = light_transform.compute_transform().rotation;
+    let to_light = rotation.inverse();
+
+    for ( index, far ) in config.bounds.iter().enumerate()
+    {   let near = if index > 0 { ( 1.0 - config.overlap_proportion ) * config.bounds[ index - 1 ] }
+            else { config.minimum_distance };
+
+        //視錐台の区切りの8つの角を、平行光源の向きの座標系で囲む
+        let ( min, max ) = [ near, *far ].into_iter()
+            .flat_map( | distance |
+            {   let height = distance * tan_half_fov;
+                let width = height * perspective.aspect_ratio;
+                [ ( -width, -height ), ( width, -height ), ( width, height ), ( -width, height ) ]
+                    .map( | ( x, y ) | to_light * camera_transform.transform_point( Vec3::new( x, y, -distance ) ) )
+            })
+            .fold( ( Vec3::MAX, Vec3::MIN ), | ( min, max ), corner | ( min.min( corner ), max.max( corner ) ) );
+
+        let color = DEBUG_CASCADE_COLORS[ index % DEBUG_CASCADE_COLORS.len() ];
+        let transform = Transform
+        {   translation: rotation * ( ( min + max ) * 0.5 ),
+            rotation,
+            scale: max - min,
+        };
+        gizmos.cuboid( transform, color );
+    }
+}
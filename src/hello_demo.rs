@@ -0,0 +1,96 @@
+use super::*;
+
+use app_state::{ SceneList, SceneEntity, ViewerScene };
+
+//------------------------------------------------------------------------------
+
+//archive/hello.rs・archive/movehello.rsの2Dテキストのデモ
+//(テキストは2Dカメラで表示する)
+
+//マーカーの準備
+#[derive( Component )] pub struct HelloWorld1;
+#[derive( Component )] pub struct HelloWorld2;
+#[derive( Component )] pub struct HelloWorld3;
+
+//2Dテキストを作る
+fn hello_text( x: f32 ) -> Text2dBundle
+{   let textstyle = TextStyle { font_size: HELLO_FONT_SIZE, ..default() };
+    let text = Text::from_section( "Hello, world!", textstyle );
+    let transform = Transform::from_xyz( x, 0.0, 0.0 );
+
+    Text2dBundle { text, transform, ..default() }
+}
+
+//hello, worldを表示する
+pub fn spawn_hello
+(   list: Res<SceneList>,
+    mut cmds: Commands,
+)
+{   if list.selected() != Some ( &ViewerScene::Hello ) { return }
+
+    info!( "Hello, world!" );
+    cmds.spawn( ( hello_text( 0.0 ), SceneEntity ) );
+}
+
+//動くhello, worldを3つ表示する
+pub fn spawn_movehello
+(   list: Res<SceneList>,
+    mut cmds: Commands,
+)
+{   if list.selected() != Some ( &ViewerScene::MoveHello ) { return }
+
+    cmds.spawn( ( hello_text( -HELLO_MOVE_RADIUS.x ), HelloWorld1, SceneEntity ) ); //マーカー１付きspawn
+    cmds.spawn( ( hello_text(  0.0                 ), HelloWorld2, SceneEntity ) ); //マーカー２付きspawn
+    cmds.spawn( ( hello_text(  HELLO_MOVE_RADIUS.x ), HelloWorld3, SceneEntity ) ); //マーカー３付きspawn
+}
+
+//------------------------------------------------------------------------------
+
+//移動
+pub fn move_helloworld
+(   mut q_transform: Query<&mut Transform, With<HelloWorld1>>, //マーカー１で検索
+    time: Res<Time>,
+    mut angle: Local<f32>, //ローカル変数
+)
+{   let Ok ( mut transform ) = q_transform.get_single_mut() else { return };
+
+    let time_delta = time.delta().as_secs_f32(); //前回の実行からの経過時間
+    *angle += 360.0 * time_delta;
+    *angle -= if *angle > 360.0 { 360.0 } else { 0.0 };
+
+    //楕円軌道の移動
+    let x = angle.to_radians().cos() * HELLO_MOVE_RADIUS.x;
+    let y = angle.to_radians().sin() * HELLO_MOVE_RADIUS.y;
+    transform.translation = Vec3::new( x, y, 0.0 );
+}
+
+//回転
+pub fn rotate_helloworld
+(   mut q_transform: Query<&mut Transform, With<HelloWorld2>>, //マーカー２で検索
+    time: Res<Time>,
+)
+{   let Ok ( mut transform ) = q_transform.get_single_mut() else { return };
+
+    let time_delta = time.delta().as_secs_f32(); //前回の実行からの経過時間
+    let angle = 360.0 * time_delta;
+    let quat = Quat::from_rotation_z( angle.to_radians() );
+
+    //回転(四元数Quatは掛け算で回る)
+    transform.rotation *= quat;
+}
+
+//拡縮
+pub fn scale_helloworld
+(   mut q_transform: Query<&mut Transform, With<HelloWorld3>>, //マーカー３で検索
+    time: Res<Time>,
+    mut angle: Local<f32>, //ローカル変数
+)
+{   let Ok ( mut transform ) = q_transform.get_single_mut() else { return };
+
+    let time_delta = time.delta().as_secs_f32(); //前回の実行からの経過時間
+    *angle += 360.0 * time_delta;
+    *angle -= if *angle > 360.0 { 360.0 } else { 0.0 };
+
+    //拡縮(sin()がマイナスになると表示が反転する)
+    transform.scale = Vec3::ONE * angle.to_radians().sin();
+}
@@ -0,0 +1,24 @@
+use super::*;
+
+//------------------------------------------------------------------------------
+
+//HUDに一定時間だけ表示するメッセージ
+#[derive( Resource, Default )]
+pub struct HudMessage
+{   text    : String,
+    shown_at: f32, //表示を始めた時刻(起動からの秒数)
+}
+
+impl HudMessage
+{   //メッセージを表示する
+    pub fn show( &mut self, text: impl Into<String>, time: &Time )
+    {   self.text = text.into();
+        self.shown_at = time.raw_elapsed_seconds(); //一時停止中も消えるように
+    }
+
+    //表示中のメッセージ(時間切れならNone)
+    pub fn text( &self, time: &Time ) -> Option<&str>
+    {   let is_expired = time.raw_elapsed_seconds() - self.shown_at > HUD_MESSAGE_SECS;
+        ( ! self.text.is_empty() && ! is_expired ).then_some( self.text.as_str() )
+    }
+}
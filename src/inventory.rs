@@ -0,0 +1,120 @@
+use super::*;
+
+use loot::Rarity;
+
+//------------------------------------------------------------------------------
+
+//同じアイテムをまとめたもの
+#[derive( Clone, Serialize, Deserialize, Reflect )]
+pub struct ItemStack
+{   pub item    : String,
+    pub rarity  : Rarity,
+    pub quantity: u32,
+}
+
+//プレイヤーのインベントリ
+#[derive( Resource, Default, Serialize, Deserialize, Reflect )]
+#[reflect( Resource )]
+pub struct Inventory { pub items: Vec<ItemStack> }
+
+impl Inventory
+{   //アイテムを入れる(同じアイテムがあれば個数を足す)
+    pub fn add( &mut self, stack: ItemStack )
+    {   match self.items.iter_mut().find( | item | item.item == stack.item )
+        {   Some ( item ) => item.quantity += stack.quantity,
+            None => self.items.push( stack ),
+        }
+    }
+}
+
+//インベントリのパネル(2Dカメラで描くテキスト)に付けるComponent
+#[derive( Component )]
+pub struct InventoryPanel;
+
+//------------------------------------------------------------------------------
+
+//インベントリのパネルを作る(ウィンドウの右上、最初は非表示)
+pub fn spawn_inventory_panel
+(   q_window: Query<&Window>,
+    mut cmds: Commands,
+)
+{   let Ok( window ) = q_window.get_single() else { return };
+    let translation = Vec3::new( window.width() / 2.0, window.height() / 2.0, 0.0 );
+    let transform = Transform::from_translation( translation );
+    let text = Text::default().with_alignment( TextAlignment::Right );
+
+    cmds.spawn( ( Text2dBundle { text, transform, text_anchor: Anchor::TopRight, ..default() }, InventoryPanel ) )
+        .insert( Visibility::Hidden );
+}
+
+//[Tab]でパネルの表示を切り替え、[F8]で保存・[Shift]+[F8]で読込をする
+pub fn operate_inventory
+(   mut q_panel: Query<&mut Visibility, With<InventoryPanel>>,
+    mut inventory: ResMut<Inventory>,
+    inkey: Res<Input<KeyCode>>,
+)
+{   if inkey.just_pressed( KeyCode::Tab )
+    {   for mut visibility in q_panel.iter_mut()
+        {   *visibility = if *visibility == Visibility::Hidden { Visibility::Visible } else { Visibility::Hidden };
+        }
+    }
+
+    if ! inkey.just_pressed( KeyCode::F8 ) { return }
+    let file = ron_file::asset_path( INVENTORY_FILE );
+    if inkey.any_pressed( [ KeyCode::ShiftLeft, KeyCode::ShiftRight ] )
+    {   match ron_file::load::<Inventory>( &file )
+        {   Ok  ( loaded ) => { *inventory = loaded; info!( "inventory loaded: {}", file.display() ) }
+            Err ( err    ) => error!( "failed to load inventory: {err}" ),
+        }
+    }
+    else
+    {   match ron_file::save( &file, &*inventory )
+        {   Ok  ( () ) => info!( "inventory saved: {}", file.display() ),
+            Err ( err ) => error!( "failed to save inventory: {err}" ),
+        }
+    }
+}
+
+//インベントリが変わったらパネルのテキストを作り直す(アイテムはレア度の色で表示)
+pub fn show_inventory
+(   mut q_panel: Query<&mut Text, With<InventoryPanel>>,
+    inventory: Res<Inventory>,
+)
+{   if ! inventory.is_changed() { return }
+    let Ok ( mut text ) = q_panel.get_single_mut() else { return };
+
+    let style = | color | TextStyle { font_size: INVENTORY_FONT_SIZE, color, ..default() };
+    let mut sections = vec![ TextSection::new( "Inventory \n", style( Color::WHITE ) ) ];
+    if inventory.items.is_empty()
+    {   sections.push( TextSection::new( "(empty) \n", style( Color::GRAY ) ) );
+    }
+    for stack in &inventory.items
+    {   let value = format!( "{} x{} \n", stack.item, stack.quantity );
+        sections.push( TextSection::new( value, style( stack.rarity.color() ) ) );
+    }
+
+    text.sections = sections;
+}
+
+//------------------------------------------------------------------------------
+
+#[cfg( test )]
+mod tests
+{   use super::*;
+
+    fn stack( item: &str, quantity: u32 ) -> ItemStack
+    {   ItemStack { item: item.to_string(), rarity: Rarity::Common, quantity }
+    }
+
+    //同じアイテムは個数を足し、違うアイテムは別に入れる
+    #[test]
+    fn add_stacks_same_items()
+    {   let mut inventory = Inventory::default();
+        inventory.add( stack( "gold", 3 ) );
+        inventory.add( stack( "gem" , 1 ) );
+        inventory.add( stack( "gold", 4 ) );
+
+        let items = inventory.items.iter().map( | item | ( item.item.as_str(), item.quantity ) ).collect::<Vec<_>>();
+        assert_eq!( items, [ ( "gold", 7 ), ( "gem", 1 ) ] );
+    }
+}
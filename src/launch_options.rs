@@ -0,0 +1,51 @@
+use super::*;
+
+//------------------------------------------------------------------------------
+
+//コマンドラインで指定された起動オプション
+//  例) cargo run -- models/helmet.glb
+//      cargo run -- --seed=42
+//      cargo run -- --hunt=10
+#[derive( Resource, Default )]
+pub struct LaunchOptions
+{   pub model: Option<String>, //表示するglTFファイル(assetsフォルダからの相対パスか絶対パス)
+    pub seed : Option<u64>,    //宝箱の中身の抽選や配置に使う乱数のシード
+    pub hunt : Option<usize>,  //宝探しモードの宝箱の数
+}
+
+//コマンドライン引数を読む
+pub fn parse() -> LaunchOptions
+{   let mut options = LaunchOptions::default();
+
+    for arg in std::env::args().skip( 1 )
+    {   let lower = arg.to_lowercase();
+        if lower.ends_with( ".gltf" ) || lower.ends_with( ".glb" )
+        {   options.model = Some ( arg );
+        }
+        else if let Some ( seed ) = arg.strip_prefix( "--seed=" )
+        {   match seed.parse()
+            {   Ok  ( seed ) => options.seed = Some ( seed ),
+                Err ( err  ) => eprintln!( "bad seed {seed:?}: {err}" ),
+            }
+        }
+        else if arg == "--hunt"
+        {   options.hunt = Some ( HUNT_CHEST_COUNT );
+        }
+        else if let Some ( count ) = arg.strip_prefix( "--hunt=" )
+        {   match count.parse()
+            {   Ok  ( count ) if count > HUNT_MAX_CHESTS =>
+                {   eprintln!( "too many chests {count}, using {HUNT_MAX_CHESTS}" );
+                    options.hunt = Some ( HUNT_MAX_CHESTS );
+                }
+                Ok  ( count ) if count > 0 => options.hunt = Some ( count ),
+                Ok  ( _ ) => eprintln!( "hunt needs at least one chest" ),
+                Err ( err ) => eprintln!( "bad chest count {count:?}: {err}" ),
+            }
+        }
+        else
+        {   eprintln!( "unknown argument: {arg}" );
+        }
+    }
+
+    options
+}
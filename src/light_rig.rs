@@ -0,0 +1,505 @@
+use super::*;
+
+use bevy::pbr::{ CascadeShadowConfig, CascadeShadowConfigBuilder };
+
+//------------------------------------------------------------------------------
+
+//光源の種類
+#[derive( Clone, Copy, PartialEq, Debug, Serialize, Deserialize, Reflect )]
+pub enum LightKind { Directional, Point, Spot }
+
+//光源の設定(light_rig.ronの1項目)
+//  intensityはDirectionalならilluminance(lux)、Point・Spotならintensity(lm)
+#[derive( Clone, PartialEq, Debug, Serialize, Deserialize )]
+#[serde( default, deny_unknown_fields )]
+pub struct LightDesc
+{   pub kind           : LightKind,
+    pub position       : [ f32; 3 ],
+    pub target         : [ f32; 3 ], //照らす先(向きを決める)
+    pub color          : [ f32; 3 ], //RGB
+    pub intensity      : f32,
+    pub shadows        : bool,       //影を描画するか
+    pub range          : f32,        //Point・Spotの届く距離
+    pub outer_angle    : f32,        //Spotの外側の角度(度)
+    pub inner_angle    : f32,        //Spotの内側の角度(度)
+    pub first_cascade  : f32,        //Directionalの影の最初のカスケードの距離
+    pub shadow_distance: f32,        //Directionalの影が落ちる最大距離
+}
+
+impl Default for LightDesc
+{   fn default() -> Self
+    {   Self
+        {   kind           : LightKind::Directional,
+            position       : LIGHT_POSITION.to_array(),
+            target         : [ 0.0; 3 ],
+            color          : [ 1.0; 3 ],
+            intensity      : LIGHT_BRIGHTNESS,
+            shadows        : true,
+            range          : LIGHT_RANGE,
+            outer_angle    : LIGHT_SPOT_OUTER_ANGLE,
+            inner_angle    : LIGHT_SPOT_INNER_ANGLE,
+            first_cascade  : LIGHT_FIRST_CASCADE,
+            shadow_distance: LIGHT_SHADOW_DISTANCE,
+        }
+    }
+}
+
+impl LightDesc
+{   //値をチェックする
+    fn validate( &self ) -> Result<(), String>
+    {   if self.position == self.target
+        {   return Err ( "light position and target must be different".to_string() );
+        }
+        if self.color.iter().any( | value | ! ( 0.0..=1.0 ).contains( value ) )
+        {   return Err ( "light color values must be between 0.0 and 1.0".to_string() );
+        }
+        if ! self.intensity.is_finite() || self.intensity < 0.0 || ! self.range.is_finite() || self.range <= 0.0
+        {   return Err ( "light intensity and range must be positive numbers".to_string() );
+        }
+        if ! ( 0.0..=self.outer_angle ).contains( &self.inner_angle ) || self.outer_angle >= 90.0
+        {   return Err ( "spot angles must be 0 <= inner <= outer < 90".to_string() );
+        }
+        if ! ( LIGHT_MIN_CASCADE < self.first_cascade && self.first_cascade < self.shadow_distance )
+        {   return Err ( format!( "cascade distances must be {LIGHT_MIN_CASCADE} < first_cascade < shadow_distance" ) );
+        }
+        Ok (())
+    }
+}
+
+//環境光と光源の一式(light_rig.ron)
+#[derive( PartialEq, Debug, Serialize, Deserialize )]
+#[serde( default, deny_unknown_fields )]
+pub struct LightRigDesc
+{   pub ambient_color     : [ f32; 3 ],
+    pub ambient_brightness: f32,
+    pub lights: Vec<LightDesc>,
+}
+
+//元の光源(平行光源1つ)
+impl Default for LightRigDesc
+{   fn default() -> Self
+    {   let AmbientLight { color, brightness } = AmbientLight::default();
+        let [ r, g, b, _ ] = color.as_rgba_f32();
+        Self
+        {   ambient_color     : [ r, g, b ],
+            ambient_brightness: brightness,
+            lights: vec![ LightDesc::default() ],
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//光源リグの光源に付けるComponent
+#[derive( Component, Reflect )]
+#[reflect( Component )]
+pub struct RigLight
+{   pub kind       : LightKind,
+    pub target     : Vec3, //照らす先
+    first_cascade  : f32,  //Directionalの影の最初のカスケードの距離
+    shadow_distance: f32,  //Directionalの影が落ちる最大距離
+}
+
+//マウスの左ドラッグ([L]を押しながら)で向きを変えられる光源に付けるComponent
+//(平行光源だけに付ける。極座標は照らす先から見た光源の位置)
+#[derive( Component, Reflect )]
+#[reflect( Component )]
+pub struct OrbitLight { pub( super ) orbit: Orbit }
+
+impl Default for OrbitLight
+{   fn default() -> Self
+    {   Self { orbit: Orbit::from_vec3( LIGHT_POSITION ) }
+    }
+}
+
+//光源リグの編集の状態
+#[derive( Resource, Default )]
+pub struct LightRig
+{   pub selected: Option<Entity>, //編集中の光源
+    next_id: usize,               //光源の名前に付ける通し番号
+}
+
+//------------------------------------------------------------------------------
+
+//positionからtargetへ向けたTransform(真上・真下を向く時はup軸を変える)
+pub fn aim( position: Vec3, target: Vec3 ) -> Transform
+{   let direction = ( target - position ).normalize_or_zero();
+    let up = if direction.dot( Vec3::Y ).abs() > LIGHT_UP_THRESHOLD { Vec3::Z } else { Vec3::Y };
+    Transform::from_translation( position ).looking_at( target, up )
+}
+
+//カスケードシャドウの設定
+fn cascade( first_cascade: f32, shadow_distance: f32 ) -> CascadeShadowConfig
+{   CascadeShadowConfigBuilder
+    {   first_cascade_far_bound: first_cascade,
+        maximum_distance: shadow_distance,
+        minimum_distance: LIGHT_MIN_CASCADE,
+        ..default()
+    }
+    .build()
+}
+
+//影のカスケードの距離を倍率で変える(is_firstなら最初のカスケード、そうでなければ影が落ちる最大距離)
+fn scale_cascade( first_cascade: f32, shadow_distance: f32, step: f32, is_first: bool ) -> ( f32, f32 )
+{   if is_first
+    {   //影が落ちる距離が短いと上限が下限を下回るので、上限を下限以上にしておく
+        let min = LIGHT_MIN_CASCADE * 2.0;
+        let max = ( shadow_distance * 0.5 ).max( min );
+        ( ( first_cascade * step ).clamp( min, max ), shadow_distance )
+    }
+    else
+    {   ( first_cascade, ( shadow_distance * step ).max( first_cascade * 2.0 ) )
+    }
+}
+
+//光源を作る
+fn spawn_light( cmds: &mut Commands, rig: &mut LightRig, desc: &LightDesc ) -> Entity
+{   let transform = aim( Vec3::from( desc.position ), Vec3::from( desc.target ) );
+    let color = Color::rgb( desc.color[ 0 ], desc.color[ 1 ], desc.color[ 2 ] );
+    let mut entity_cmds = match desc.kind
+    {   LightKind::Directional => cmds.spawn( DirectionalLightBundle
+        {   directional_light: DirectionalLight
+            {   color,
+                illuminance: desc.intensity,
+                shadows_enabled: desc.shadows,
+                ..default()
+            },
+            cascade_shadow_config: cascade( desc.first_cascade, desc.shadow_distance ),
+            transform,
+            ..default()
+        }),
+        LightKind::Point => cmds.spawn( PointLightBundle
+        {   point_light: PointLight
+            {   color,
+                intensity: desc.intensity,
+                range: desc.range,
+                shadows_enabled: desc.shadows,
+                ..default()
+            },
+            transform,
+            ..default()
+        }),
+        LightKind::Spot => cmds.spawn( SpotLightBundle
+        {   spot_light: SpotLight
+            {   color,
+                intensity: desc.intensity,
+                range: desc.range,
+                shadows_enabled: desc.shadows,
+                outer_angle: desc.outer_angle.to_radians(),
+                inner_angle: desc.inner_angle.to_radians(),
+                ..default()
+            },
+            transform,
+            ..default()
+        }),
+    };
+
+    rig.next_id += 1;
+    entity_cmds.insert
+    ((  RigLight
+        {   kind: desc.kind,
+            target: Vec3::from( desc.target ),
+            first_cascade: desc.first_cascade,
+            shadow_distance: desc.shadow_distance,
+        },
+        Name::new( format!( "light {}", rig.next_id ) ), //シーンの状態の保存・読込で使う
+    ));
+    if desc.kind == LightKind::Directional
+    {   let orbit = Orbit::from_vec3( Vec3::from( desc.position ) - Vec3::from( desc.target ) );
+        entity_cmds.insert( OrbitLight { orbit } );
+    }
+    entity_cmds.id()
+}
+
+//光源リグを作る(値がおかしい光源はログに出して作らない)
+fn spawn_rig( cmds: &mut Commands, rig: &mut LightRig, desc: &LightRigDesc )
+{   let [ r, g, b ] = desc.ambient_color;
+    cmds.insert_resource( AmbientLight { color: Color::rgb( r, g, b ), brightness: desc.ambient_brightness } );
+
+    for ( i, light ) in desc.lights.iter().enumerate()
+    {   match light.validate()
+        {   Ok  ( () ) => { spawn_light( cmds, rig, light ); }
+            Err ( message ) => error!( "{LIGHT_RIG_FILE}: lights[{i}]: {message}" ),
+        }
+    }
+}
+
+//光源の値を種類によらず読む( 色, 強さ, 影, 届く距離, 外側・内側の角度(度) )
+type LightValues = ( Color, f32, bool, f32, f32, f32 );
+fn light_values
+(   directional: Option<&DirectionalLight>,
+    point: Option<&PointLight>,
+    spot: Option<&SpotLight>,
+) -> Option<LightValues>
+{   if let Some ( light ) = directional
+    {   return Some ( ( light.color, light.illuminance, light.shadows_enabled, LIGHT_RANGE, LIGHT_SPOT_OUTER_ANGLE, LIGHT_SPOT_INNER_ANGLE ) )
+    }
+    if let Some ( light ) = point
+    {   return Some ( ( light.color, light.intensity, light.shadows_enabled, light.range, LIGHT_SPOT_OUTER_ANGLE, LIGHT_SPOT_INNER_ANGLE ) )
+    }
+    spot.map( | light |
+        ( light.color, light.intensity, light.shadows_enabled, light.range, light.outer_angle.to_degrees(), light.inner_angle.to_degrees() )
+    )
+}
+
+//------------------------------------------------------------------------------
+
+//起動時に光源リグを作る(ファイルがなければ元の平行光源1つ)
+pub fn setup_light_rig
+(   mut rig: ResMut<LightRig>,
+    mut cmds: Commands,
+)
+{   let file = ron_file::asset_path( LIGHT_RIG_FILE );
+    let desc = if ! file.exists() { LightRigDesc::default() } else
+    {   match ron_file::load::<LightRigDesc>( &file )
+        {   Ok  ( desc ) => desc,
+            Err ( err  ) => { error!( "failed to load light rig: {err}" ); LightRigDesc::default() }
+        }
+    };
+    spawn_rig( &mut cmds, &mut rig, &desc );
+}
+
+//マウスで向きを変える光源(選択中の光源が回せなければ最初に作った回せる光源)
+pub fn orbit_light_target( rig: &LightRig, lights: impl Iterator<Item = Entity> ) -> Option<Entity>
+{   let lights: Vec<Entity> = lights.collect();
+    rig.selected.filter( | selected | lights.contains( selected ) )
+        .or_else( || lights.iter().min().copied() )
+}
+
+//極座標に合わせて光源の位置と向きを更新する
+pub fn move_orbit_lights
+(   mut q_lights: Query<( &OrbitLight, &RigLight, &mut Transform ), Changed<OrbitLight>>,
+)
+{   for ( orbit_light, light, mut transform ) in q_lights.iter_mut()
+    {   *transform = aim( light.target + orbit_light.orbit.into_vec3(), light.target );
+    }
+}
+
+//光源リグの編集で使うQuery
+type LightQuery<'w, 's> = Query
+<   'w, 's,
+    (   Entity,
+        &'static mut RigLight,
+        &'static Transform,
+        Option<&'static mut DirectionalLight>,
+        Option<&'static mut PointLight>,
+        Option<&'static mut SpotLight>,
+        Option<&'static mut CascadeShadowConfig>,
+    ),
+>;
+
+//実行中に光源リグを編集する
+//  [PageUp]/[PageDown]:光源の選択 [Insert]:点光源を追加([Shift]:スポットライト [Ctrl]:平行光源) [Delete]:削除
+//  [Home]/[End]:強さ([Shift]:環境光の明るさ [Ctrl]:影が落ちる距離 [Ctrl]+[Shift]:最初のカスケードの距離)
+//  [C]:色の切換([Shift]:環境光の色) [B]:影のON/OFF [F11]:保存 [Shift]+[F11]:読込
+pub fn edit_light_rig
+(   mut q_lights: LightQuery,
+    q_camera: Query<&OrbitCamera>,
+    mut rig: ResMut<LightRig>,
+    mut ambient: ResMut<AmbientLight>,
+    inkey: Res<Input<KeyCode>>,
+    mut cmds: Commands,
+)
+{   let is_shift = inkey.any_pressed( [ KeyCode::ShiftLeft,   KeyCode::ShiftRight   ] );
+    let is_ctrl  = inkey.any_pressed( [ KeyCode::ControlLeft, KeyCode::ControlRight ] );
+
+    //光源の選択(作った順)
+    let mut lights: Vec<Entity> = q_lights.iter().map( | ( entity, .. ) | entity ).collect();
+    lights.sort();
+    let index = rig.selected.and_then( | selected | lights.iter().position( | entity | *entity == selected ) );
+    if rig.selected.is_some() && index.is_none() { rig.selected = None } //消されていた
+    let len = lights.len();
+    if len > 0
+    {   if inkey.just_pressed( KeyCode::PageDown )
+        {   rig.selected = Some ( lights[ index.map_or( 0, | i | ( i + 1 ) % len ) ] );
+        }
+        if inkey.just_pressed( KeyCode::PageUp )
+        {   rig.selected = Some ( lights[ index.map_or( len - 1, | i | ( i + len - 1 ) % len ) ] );
+        }
+    }
+
+    //光源の追加(注目点を照らす)と削除
+    if inkey.just_pressed( KeyCode::Insert )
+    {   let focus = q_camera.get_single().map_or( Vec3::ZERO, | camera | camera.focus );
+        let ( kind, offset, intensity ) =
+            if is_ctrl { ( LightKind::Directional, LIGHT_POSITION, LIGHT_BRIGHTNESS ) }
+            else if is_shift { ( LightKind::Spot, LIGHT_SPOT_OFFSET, LIGHT_POINT_INTENSITY ) }
+            else { ( LightKind::Point, LIGHT_POINT_OFFSET, LIGHT_POINT_INTENSITY ) };
+        let desc = LightDesc
+        {   kind,
+            position: ( focus + offset ).to_array(),
+            target: focus.to_array(),
+            intensity,
+            ..default()
+        };
+        let entity = spawn_light( &mut cmds, &mut rig, &desc );
+        rig.selected = Some ( entity );
+    }
+    if inkey.just_pressed( KeyCode::Delete )
+    {   if let Some ( entity ) = rig.selected.take() { cmds.entity( entity ).despawn_recursive(); }
+    }
+
+    //環境光
+    let step = if inkey.just_pressed( KeyCode::Home ) { LIGHT_INTENSITY_STEP }
+        else if inkey.just_pressed( KeyCode::End ) { LIGHT_INTENSITY_STEP.recip() }
+        else { 1.0 };
+    if is_shift && ! is_ctrl
+    {   if step != 1.0 { ambient.brightness *= step; }
+        if inkey.just_pressed( KeyCode::C ) { ambient.color = next_color( ambient.color ); }
+    }
+
+    //保存と読込
+    if inkey.just_pressed( KeyCode::F11 )
+    {   let file = ron_file::asset_path( LIGHT_RIG_FILE );
+        if is_shift
+        {   match ron_file::load::<LightRigDesc>( &file )
+            {   Ok  ( desc ) =>
+                {   for entity in lights { cmds.entity( entity ).despawn_recursive(); }
+                    rig.selected = None;
+                    spawn_rig( &mut cmds, &mut rig, &desc );
+                    info!( "light rig loaded: {}", file.display() );
+                }
+                Err ( err ) => error!( "failed to load light rig: {err}" ),
+            }
+        }
+        else
+        {   let desc = LightRigDesc
+            {   ambient_color: { let [ r, g, b, _ ] = ambient.color.as_rgba_f32(); [ r, g, b ] },
+                ambient_brightness: ambient.brightness,
+                lights: lights.iter().filter_map( | entity |
+                {   let ( _, light, transform, directional, point, spot, _ ) = q_lights.get( *entity ).ok()?;
+                    let ( color, intensity, shadows, range, outer_angle, inner_angle ) =
+                        light_values( directional, point, spot )?;
+                    let [ r, g, b, _ ] = color.as_rgba_f32();
+                    Some ( LightDesc
+                    {   kind: light.kind,
+                        position: transform.translation.to_array(),
+                        target: light.target.to_array(),
+                        color: [ r, g, b ],
+                        intensity,
+                        shadows,
+                        range,
+                        outer_angle,
+                        inner_angle,
+                        first_cascade: light.first_cascade,
+                        shadow_distance: light.shadow_distance,
+                    })
+                })
+                .collect(),
+            };
+            match ron_file::save( &file, &desc )
+            {   Ok  ( () ) => info!( "light rig saved: {}", file.display() ),
+                Err ( err ) => error!( "failed to save light rig: {err}" ),
+            }
+        }
+    }
+
+    //選択中の光源
+    let Some ( selected ) = rig.selected else { return };
+    let Ok ( ( _, mut light, _, mut directional, mut point, mut spot, cascade_config ) ) = q_lights.get_mut( selected ) else { return };
+
+    //影が落ちる距離(平行光源だけ)
+    if is_ctrl && step != 1.0
+    {   let ( first_cascade, shadow_distance ) = scale_cascade( light.first_cascade, light.shadow_distance, step, is_shift );
+        light.first_cascade = first_cascade;
+        light.shadow_distance = shadow_distance;
+        if let Some ( mut config ) = cascade_config { *config = cascade( light.first_cascade, light.shadow_distance ); }
+        return
+    }
+    if is_shift { return } //環境光の操作
+
+    //強さ・色・影
+    let ( color, intensity, shadows ) =
+        if let Some ( light ) = directional.as_deref_mut() { ( &mut light.color, &mut light.illuminance, &mut light.shadows_enabled ) }
+        else if let Some ( light ) = point.as_deref_mut() { ( &mut light.color, &mut light.intensity, &mut light.shadows_enabled ) }
+        else if let Some ( light ) = spot.as_deref_mut() { ( &mut light.color, &mut light.intensity, &mut light.shadows_enabled ) }
+        else { return };
+    if step != 1.0 { *intensity *= step; }
+    if inkey.just_pressed( KeyCode::C ) { *color = next_color( *color ); }
+    if inkey.just_pressed( KeyCode::B ) { *shadows = ! *shadows; }
+}
+
+//光の色の候補の次の色(候補にない色なら最初の色)
+fn next_color( color: Color ) -> Color
+{   let index = LIGHT_COLORS.iter().position( | candidate | *candidate == color );
+    LIGHT_COLORS[ index.map_or( 0, | i | ( i + 1 ) % LIGHT_COLORS.len() ) ]
+}
+
+//HUDに表示する編集中の光源と環境光の状態
+pub fn status
+(   rig: &LightRig,
+    q_lights: &Query<( &Name, &RigLight, Option<&DirectionalLight>, Option<&PointLight>, Option<&SpotLight> )>,
+    ambient: &AmbientLight,
+) -> Option<String>
+{   let ( name, light, directional, point, spot ) = q_lights.get( rig.selected? ).ok()?;
+    let ( color, intensity, shadows, .. ) = light_values( directional, point, spot )?;
+    let [ r, g, b, _ ] = color.as_rgba_f32();
+    let shadows = if shadows { "on" } else { "off" };
+    let cascade = if light.kind != LightKind::Directional { String::new() }
+        else { format!( " cascade:{:.1}-{:.0}", light.first_cascade, light.shadow_distance ) };
+    Some ( format!
+    (   " {name}:{:?} intensity:{intensity:.0}\n color:({r:.2},{g:.2},{b:.2}) shadows:{shadows}{cascade}\n ambient:{:.2}",
+        light.kind, ambient.brightness,
+    ))
+}
+
+//------------------------------------------------------------------------------
+
+#[cfg( test )]
+mod tests
+{   use super::*;
+
+    //おかしな値の光源は通さない
+    #[test]
+    fn validate_rejects_bad_lights()
+    {   assert!( LightDesc::default().validate().is_ok() );
+
+        let bad_lights =
+        [   LightDesc { target: LIGHT_POSITION.to_array(), ..default() },        //位置と照らす先が同じ
+            LightDesc { color: [ 1.5, 1.0, 1.0 ], ..default() },                 //色が範囲外
+            LightDesc { intensity: -1.0, ..default() },                          //強さが負
+            LightDesc { range: 0.0, ..default() },                               //届く距離が0
+            LightDesc { inner_angle: 40.0, outer_angle: 30.0, ..default() },     //内側が外側より広い
+            LightDesc { outer_angle: 90.0, ..default() },                        //外側が90度以上
+            LightDesc { first_cascade: LIGHT_MIN_CASCADE, ..default() },         //最初のカスケードが近すぎる
+            LightDesc { first_cascade: 10.0, shadow_distance: 5.0, ..default() }, //カスケードの順番が逆
+        ];
+        for light in bad_lights
+        {   assert!( light.validate().is_err(), "{light:?}" );
+        }
+    }
+
+    //知らない項目のある光源リグは読み込まない
+    #[test]
+    fn parse_rejects_unknown_fields()
+    {   assert!( ron::from_str::<LightRigDesc>( "( lights: [ ( kind: Point, colour: ( 1.0, 1.0, 1.0 ) ) ] )" ).is_err() );
+        assert!( ron::from_str::<LightRigDesc>( "( lights: [ ( kind: Area ) ] )" ).is_err() );
+    }
+
+    //assets/light_rig.ronは読み込めて、保存して読み直しても同じになる
+    #[test]
+    fn light_rig_file_round_trips()
+    {   let file = ron_file::asset_path( LIGHT_RIG_FILE );
+        let desc: LightRigDesc = ron_file::load( &file ).unwrap_or_else( | err | panic!( "{err}" ) );
+        for light in &desc.lights
+        {   assert!( light.validate().is_ok(), "{light:?}" );
+        }
+
+        let text = ron::ser::to_string_pretty( &desc, ron::ser::PrettyConfig::default() ).unwrap();
+        let reloaded: LightRigDesc = ron::from_str( &text ).unwrap();
+        assert_eq!( reloaded, desc );
+    }
+
+    //最初のカスケードは下限～影が落ちる距離の半分に収まり、影が落ちる距離が短くても落ちない
+    #[test]
+    fn scale_cascade_stays_in_range()
+    {   assert_eq!( scale_cascade( 5.0, 100.0, 2.0, true ), ( 10.0, 100.0 ) );
+        assert_eq!( scale_cascade( 40.0, 100.0, 2.0, true ), ( 50.0, 100.0 ) );
+        assert_eq!( scale_cascade( 0.15, 100.0, 0.5, true ), ( LIGHT_MIN_CASCADE * 2.0, 100.0 ) );
+        assert_eq!( scale_cascade( 0.15, 0.3, LIGHT_INTENSITY_STEP, true ), ( LIGHT_MIN_CASCADE * 2.0, 0.3 ) );
+
+        //影が落ちる距離は最初のカスケードの2倍より短くしない
+        assert_eq!( scale_cascade( 5.0, 100.0, 0.5, false ), ( 5.0, 50.0 ) );
+        assert_eq!( scale_cascade( 5.0, 12.0, 0.5, false ), ( 5.0, 10.0 ) );
+    }
+}
@@ -0,0 +1,84 @@
+use super::*;
+
+use chest::ChestLock;
+use hud_message::HudMessage;
+use picking::MeshClicked;
+
+//------------------------------------------------------------------------------
+
+//鍵がかかっている宝箱のルートに付けるComponent
+#[derive( Component, Reflect )]
+#[reflect( Component )]
+pub struct Locked { pub key_id: String }
+
+//拾える鍵に付けるComponent
+#[derive( Component, Reflect )]
+#[reflect( Component )]
+pub struct ChestKey { pub key_id: String }
+
+//拾った鍵
+#[derive( Resource, Reflect, Default )]
+#[reflect( Resource )]
+pub struct KeyRing { pub key_ids: Vec<String> }
+
+//------------------------------------------------------------------------------
+
+//クリックされた鍵を拾う
+pub fn pick_up_keys
+(   q_key: Query<&ChestKey>,
+    q_parent: Query<&Parent>,
+    mut e_clicked: EventReader<MeshClicked>,
+    mut key_ring: ResMut<KeyRing>,
+    mut message: ResMut<HudMessage>,
+    time: Res<Time>,
+    mut cmds: Commands,
+)
+{   for clicked in e_clicked.iter()
+    {   //[Shift]+クリックは部品の選択なので無視する
+        if clicked.is_shift { continue }
+
+        let is_key = | entity | q_key.contains( entity );
+        let Some ( entity ) = picking::find_ancestor( clicked.entity, &q_parent, is_key ) else { continue };
+        let Ok ( key ) = q_key.get( entity ) else { continue };
+
+        key_ring.key_ids.push( key.key_id.clone() );
+        message.show( format!( "Picked up the key \"{}\"", key.key_id ), &time );
+        cmds.entity( entity ).despawn_recursive();
+    }
+}
+
+//クリックされた錠前を、持っている鍵で開ける
+pub fn unlock_by_click
+(   q_lock: Query<(), With<ChestLock>>,
+    q_locked: Query<&Locked>,
+    q_parent: Query<&Parent>,
+    mut e_clicked: EventReader<MeshClicked>,
+    mut key_ring: ResMut<KeyRing>,
+    mut message: ResMut<HudMessage>,
+    time: Res<Time>,
+    mut cmds: Commands,
+)
+{   for clicked in e_clicked.iter()
+    {   //[Shift]+クリックは部品の選択なので無視する
+        if clicked.is_shift { continue }
+
+        //錠前(鍵穴を含む)と、その宝箱
+        let is_lock = | entity | q_lock.contains( entity );
+        let Some ( lock ) = picking::find_ancestor( clicked.entity, &q_parent, is_lock ) else { continue };
+        let is_locked = | entity | q_locked.contains( entity );
+        let Some ( chest ) = picking::find_ancestor( lock, &q_parent, is_locked ) else { continue };
+        let Ok ( locked ) = q_locked.get( chest ) else { continue };
+
+        //鍵を持っていなければ開けられない
+        let Some ( index ) = key_ring.key_ids.iter().position( | id | *id == locked.key_id ) else
+        {   message.show( format!( "This lock needs the key \"{}\"", locked.key_id ), &time );
+            continue
+        };
+
+        //鍵を使って錠前を外す
+        key_ring.key_ids.remove( index );
+        message.show( "Unlocked!", &time );
+        cmds.entity( chest ).remove::<Locked>();
+        cmds.entity( lock ).despawn_recursive();
+    }
+}
@@ -0,0 +1,212 @@
+use super::*;
+
+use std::collections::HashMap;
+use rand::{ Rng, SeedableRng, rngs::StdRng };
+use chest_lid::LidOpened;
+use hud_message::HudMessage;
+use inventory::{ Inventory, ItemStack };
+use launch_options::LaunchOptions;
+
+//------------------------------------------------------------------------------
+
+//アイテムのレア度
+#[derive( Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Reflect )]
+pub enum Rarity { Common, Uncommon, Rare, Epic, Legendary }
+
+impl Rarity
+{   //インベントリに表示する時の色
+    pub fn color( self ) -> Color
+    {   match self
+        {   Self::Common    => Color::WHITE,
+            Self::Uncommon  => Color::rgb( 0.3, 0.9, 0.3 ),
+            Self::Rare      => Color::rgb( 0.3, 0.5, 1.0 ),
+            Self::Epic      => Color::rgb( 0.7, 0.3, 0.9 ),
+            Self::Legendary => Color::ORANGE,
+        }
+    }
+}
+
+//ドロップテーブルの1行(weightの比率で選ばれ、個数はmin～maxからランダム)
+#[derive( Deserialize )]
+#[serde( deny_unknown_fields )]
+pub struct LootEntry
+{   pub item  : String,
+    pub rarity: Rarity,
+    pub weight: u32,
+    #[serde( default = "one" )]
+    pub min   : u32,
+    #[serde( default = "one" )]
+    pub max   : u32,
+}
+
+//ドロップテーブル(rolls回だけ抽選する)
+#[derive( Deserialize )]
+#[serde( deny_unknown_fields )]
+pub struct LootTable
+{   #[serde( default = "one" )]
+    pub rolls  : u32,
+    pub entries: Vec<LootEntry>,
+}
+
+fn one() -> u32 { 1 }
+
+//名前で引けるドロップテーブルの一覧(ファイルから読み込む)
+#[derive( Resource, Default, Deserialize )]
+#[serde( transparent )]
+pub struct LootTables { pub tables: HashMap<String, LootTable> }
+
+//抽選用の乱数(シードを指定すれば毎回同じ結果になる)
+#[derive( Resource )]
+pub struct LootRng ( pub StdRng );
+
+//中身が入っている宝箱のルートに付けるComponent(開けたら外す)
+#[derive( Component, Reflect )]
+#[reflect( Component )]
+pub struct Loot { pub table: String }
+
+//------------------------------------------------------------------------------
+
+impl LootTable
+{   //テーブルの値をチェックする
+    pub fn validate( &self ) -> Result<(), String>
+    {   if self.entries.iter().all( | entry | entry.weight == 0 )
+        {   return Err ( "loot table needs an entry with a positive weight".to_string() );
+        }
+        for entry in &self.entries
+        {   if entry.min > entry.max
+            {   return Err ( format!( "{}: min must not be greater than max", entry.item ) );
+            }
+        }
+
+        Ok (())
+    }
+
+    //抽選する(同じ乱数の状態からなら同じ結果になる)
+    pub fn roll<R: Rng>( &self, rng: &mut R ) -> Vec<ItemStack>
+    {   let total: u32 = self.entries.iter().map( | entry | entry.weight ).sum();
+        if total == 0 { return Vec::new() }
+
+        let mut stacks = Vec::new();
+        for _ in 0..self.rolls
+        {   //重みの累積で当たりの行を探す
+            let mut pick = rng.gen_range( 0..total );
+            let found = self.entries.iter().find( | entry |
+            {   if pick < entry.weight { return true }
+                pick -= entry.weight;
+                false
+            });
+            let Some ( entry ) = found else { continue };
+
+            let quantity = rng.gen_range( entry.min..=entry.max );
+            if quantity == 0 { continue }
+            stacks.push( ItemStack { item: entry.item.clone(), rarity: entry.rarity, quantity } );
+        }
+
+        stacks
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//ドロップテーブルを読み込んで、乱数を初期化する
+pub fn setup_loot
+(   launch: Res<LaunchOptions>,
+    mut cmds: Commands,
+)
+{   //起動オプションでシードが指定されていなければランダム
+    let rng = match launch.seed
+    {   Some ( seed ) => StdRng::seed_from_u64( seed ),
+        None => StdRng::from_entropy(),
+    };
+    cmds.insert_resource( LootRng ( rng ) );
+
+    //ドロップテーブル(エラーならログに出して空にする。値がおかしいテーブルは抽選で落ちるので外す)
+    let file = ron_file::asset_path( LOOT_TABLES_FILE );
+    let mut tables = match ron_file::load::<LootTables>( &file )
+    {   Ok  ( tables ) => tables,
+        Err ( err    ) => { error!( "failed to load loot tables: {err}" ); LootTables::default() }
+    };
+    tables.tables.retain( | name, table |
+    {   let result = table.validate();
+        if let Err ( message ) = &result { error!( "{}: {name}: {message}", file.display() ) }
+        result.is_ok()
+    });
+    cmds.insert_resource( tables );
+}
+
+//宝箱が開いたら中身を抽選してインベントリへ入れる
+pub fn roll_loot
+(   q_loot: Query<&Loot>,
+    tables: Res<LootTables>,
+    mut rng: ResMut<LootRng>,
+    mut inventory: ResMut<Inventory>,
+    mut e_opened: EventReader<LidOpened>,
+    mut message: ResMut<HudMessage>,
+    time: Res<Time>,
+    mut cmds: Commands,
+)
+{   for opened in e_opened.iter()
+    {   //中身は一度だけ
+        let Ok ( loot ) = q_loot.get( opened.chest ) else { continue };
+        cmds.entity( opened.chest ).remove::<Loot>();
+
+        let Some ( table ) = tables.tables.get( &loot.table ) else
+        {   warn!( "unknown loot table: {}", loot.table );
+            continue
+        };
+        let stacks = table.roll( &mut rng.0 );
+
+        //見つけたアイテムをHUDに出す
+        let found = stacks.iter()
+            .map( | stack | format!( "{} x{}", stack.item, stack.quantity ) )
+            .collect::<Vec<_>>();
+        let text = if found.is_empty() { "The chest is empty".to_string() }
+            else { format!( "Found: {}", found.join( ", " ) ) };
+        message.show( text, &time );
+
+        for stack in stacks { inventory.add( stack ); }
+    }
+}
+
+//------------------------------------------------------------------------------
+
+#[cfg( test )]
+mod tests
+{   use super::*;
+
+    fn entry( item: &str, weight: u32, min: u32, max: u32 ) -> LootEntry
+    {   LootEntry { item: item.to_string(), rarity: Rarity::Common, weight, min, max }
+    }
+
+    fn table() -> LootTable
+    {   LootTable
+        {   rolls: 8,
+            entries: vec![ entry( "gold", 5, 1, 10 ), entry( "gem", 2, 1, 3 ), entry( "sword", 1, 1, 1 ) ],
+        }
+    }
+
+    //同じシードなら同じ結果になる
+    #[test]
+    fn roll_is_reproducible_with_seed()
+    {   let table = table();
+        let roll = | seed | table.roll( &mut StdRng::seed_from_u64( seed ) )
+            .into_iter()
+            .map( | stack | ( stack.item, stack.quantity ) )
+            .collect::<Vec<_>>();
+
+        assert_eq!( roll( 42 ), roll( 42 ) );
+        assert_eq!( roll( 42 ).len(), 8 );
+    }
+
+    //重みが全部0のテーブルと、min > maxの行があるテーブルは通さない
+    #[test]
+    fn validate_rejects_bad_tables()
+    {   assert!( table().validate().is_ok() );
+
+        let zero_weight = LootTable { rolls: 1, entries: vec![ entry( "gold", 0, 1, 1 ), entry( "gem", 0, 1, 1 ) ] };
+        assert!( zero_weight.validate().is_err() );
+
+        let min_over_max = LootTable { rolls: 1, entries: vec![ entry( "gold", 1, 3, 2 ) ] };
+        assert!( min_over_max.validate().is_err() );
+    }
+}
@@ -1,6 +1,7 @@
 //external crates
 use bevy::
 {   prelude::*,
+    ecs::system::SystemParam,
     render::*, render::settings::*, render::camera::*,
     core_pipeline::clear_color::*,
     input::mouse::*,
@@ -11,52 +12,329 @@ use bevy::
 //standard library
 use std::f32::consts::*;
 
+//serialization
+use serde::{ Serialize, Deserialize };
+
 //internal submodules
 mod spawn_objs;
 mod const_defs;
 use const_defs::*;
 mod catch_input;
+mod camera_history;
+mod camera_path;
+mod camera_follow;
+mod ron_file;
+mod scene_file;
+mod chest;
+mod chest_lid;
+mod lock_key;
+mod picking;
+mod hud_message;
+mod loot;
+mod inventory;
+mod treasure_hunt;
+mod launch_options;
+mod model_viewer;
+mod app_state;
+mod hello_demo;
+mod time_control;
+mod save_load;
+mod material_library;
+mod procedural_texture;
+mod light_rig;
+mod day_night;
+mod skybox;
+mod reference_grid;
+mod debug_overlay;
+mod render_mode;
+mod part_visibility;
 
 //------------------------------------------------------------------------------
 
 fn main()
 {   //Note：手元の環境だとVulkanのままでは影が描画されなかったので、DX12へ切り替えた。
     let backends = Some ( Backends::DX12 );
-    let wgpu_settings = WgpuSettings { backends, ..default() };
-    let backend_dx12 = RenderPlugin { wgpu_settings };
+    //ワイヤーフレームの表示モード(render_mode)に必要な機能
+    let features = WgpuFeatures::POLYGON_MODE_LINE;
+    let wgpu_settings = WgpuSettings { features, ..default() };
+    let backend_dx12 = RenderPlugin { wgpu_settings: WgpuSettings { backends, ..wgpu_settings.clone() } };
+    let backend_default = RenderPlugin { wgpu_settings };
 
     App::new()
+        //コマンドラインの起動オプション
+        .insert_resource( launch_options::parse() )
+
         //DefaultPluginsに各種の面倒を見てもらう
         .add_plugins
         (   DefaultPlugins
-                //Note：この行をコメントアウトするとデフォルトのbackend
-                /*.set( backend_dx12 )*/
+                //Note：backend_defaultをbackend_dx12に替えるとDX12を使う
+                .set( backend_default )
         )
+        .add_plugins( bevy::pbr::wireframe::WireframePlugin ) //ワイヤーフレームの表示モード
+
+        //アプリの状態(タイトル・シーンの選択・ビューア・一時停止)
+        .add_state::<app_state::AppState>()
 
         //各種オブジェクトを作成する
         .add_systems
         (   Startup, 
-            (   spawn_objs::camera3d_and_light, //3Dカメラとライト
-                spawn_objs::locked_chest,       //3Dオブジェクト(宝箱)
-                spawn_objs::camera2d,           //2Dカメラ(情報表示用)
-                spawn_objs::display_board,      //UIテキスト(情報表示用)
+            (   spawn_objs::camera3d_and_light,    //3Dカメラ
+                spawn_objs::camera2d,              //2Dカメラ(情報表示用)
+                spawn_objs::display_board,         //UIテキスト(情報表示用)
+                inventory::spawn_inventory_panel,  //インベントリのパネル(2Dカメラで表示)
+                material_library::setup_materials, //名前付きのマテリアル
+                light_rig::setup_light_rig,        //光源と環境光
+                skybox::setup_skybox,              //空の画像
+                loot::setup_loot,                  //ドロップテーブルと乱数
+                app_state::setup_scenes,           //シーンの一覧
+            )
+        )
+
+        //タイトル画面
+        .add_systems( OnEnter ( app_state::AppState::Title ), ( app_state::unload_scene, app_state::spawn_title ) )
+        .add_systems( OnExit  ( app_state::AppState::Title ), app_state::despawn_menu )
+        .add_systems( Update, app_state::title_input.run_if( in_state( app_state::AppState::Title ) ) )
+
+        //シーンの選択画面
+        .add_systems( OnEnter ( app_state::AppState::SceneSelect ), ( app_state::unload_scene, app_state::spawn_scene_select ) )
+        .add_systems( OnExit  ( app_state::AppState::SceneSelect ), app_state::despawn_menu )
+        .add_systems
+        (   Update,
+            (   app_state::scene_select_input, //選択の操作
+                app_state::show_scene_select,  //一覧の表示
+            )
+            .chain() //実行順を固定
+            .run_if( in_state( app_state::AppState::SceneSelect ) )
+        )
+
+        //ビューア(選んだシーンを作る。一時停止から戻った時は作らない)
+        //シーンの状態を読み込んだ時は作り直してから状態を書き戻す
+        .add_systems
+        (   Update,
+            (   save_load::save_or_load,          //[F5]:シーンの状態を保存 [F9]:読込
+                app_state::unload_scene           //読み込んだらシーンを作り直す
+                    .run_if( resource_added::<save_load::PendingLoad>() ),
+                (   spawn_objs::locked_chest,     //3Dオブジェクト(宝箱)
+                    treasure_hunt::spawn_hunt,    //宝探しモードの地面と宝箱
+                    model_viewer::spawn_model,    //glTFモデル
+                    hello_demo::spawn_hello,      //2Dテキスト
+                    hello_demo::spawn_movehello,  //動く2Dテキスト
+                    app_state::mark_scene_loaded, //作ったことを記録
+                )
+                .chain() //実行順を固定
+                .run_if( app_state::is_scene_unloaded ),
+            )
+            .chain() //実行順を固定
+            .run_if( in_state( app_state::AppState::Viewer ) )
+        )
+        .add_systems
+        (   PostUpdate,
+            save_load::apply_pending_load //読み込んだ状態を書き戻す
+                .before( bevy::transform::TransformSystem::TransformPropagate )
+        )
+        .add_systems( Update, app_state::viewer_input.run_if( in_state( app_state::AppState::Viewer ) ) ) //[Esc]:シーンの選択へ [P]:一時停止
+        .add_systems
+        (   Update,
+            (   hello_demo::move_helloworld,   //2Dテキストの移動
+                hello_demo::rotate_helloworld, //2Dテキストの回転
+                hello_demo::scale_helloworld,  //2Dテキストの拡縮
+            )
+            .run_if( app_state::in_viewer ) //一時停止中は時間が止まる(コマ送りで動く)
+        )
+
+        //一時停止(時間を止める)
+        .init_resource::<time_control::TimeControl>()
+        .add_systems( OnEnter ( app_state::AppState::Paused ), ( app_state::spawn_paused, time_control::pause_time ) )
+        .add_systems( OnExit  ( app_state::AppState::Paused ), ( app_state::despawn_menu, time_control::resume_time ) )
+        .add_systems
+        (   Update,
+            (   app_state::paused_input,          //[P]/[Esc]:ビューアへ戻る
+                time_control::request_frame_step, //[N]:コマ送り
+            )
+            .run_if( in_state( app_state::AppState::Paused ) )
+        )
+        .add_systems( Update, time_control::change_time_scale.run_if( app_state::in_viewer ) ) //[-]/[=]/[0]:時間の速さ
+        .add_systems( Last, time_control::finish_frame_step ) //コマ送りの終わり
+
+        //視点の履歴
+        .init_resource::<camera_history::CameraHistory>()
+
+        //ズームのモードとキー操作の設定
+        .init_resource::<catch_input::ZoomMode>()
+        .init_resource::<catch_input::KeyboardSettings>()
+
+        //カメラパス
+        .init_resource::<camera_path::CameraPath>()
+        .init_resource::<camera_path::PathPlayer>()
+
+        //シーンの状態の保存・読込で使う型
+        .register_type::<Orbit>()
+        .register_type::<OrbitCamera>()
+        .register_type::<DisplayBoard>()
+        .register_type::<app_state::SceneEntity>()
+        .register_type::<chest::TreasureChest>()
+        .register_type::<chest::ChestBody>()
+        .register_type::<chest::ChestLid>()
+        .register_type::<chest::ChestLock>()
+        .register_type::<chest::ChestKeyhole>()
+        .register_type::<chest_lid::LidHinge>()
+        .register_type::<lock_key::Locked>()
+        .register_type::<lock_key::ChestKey>()
+        .register_type::<lock_key::KeyRing>()
+        .register_type::<loot::Rarity>()
+        .register_type::<loot::Loot>()
+        .register_type::<inventory::ItemStack>()
+        .register_type::<inventory::Inventory>()
+        .register_type::<Vec<String>>()               //KeyRingの中身
+        .register_type::<Vec<inventory::ItemStack>>() //Inventoryの中身
+        .register_type::<treasure_hunt::HuntChest>()
+        .register_type::<treasure_hunt::HuntRound>()
+        .register_type::<camera_follow::CameraTarget>()
+        .register_type::<camera_follow::Followable>()
+        .register_type::<catch_input::ZoomMode>()
+        .register_type::<catch_input::KeyboardSettings>()
+        .register_type::<save_load::SavePath>()
+        .register_type::<save_load::SaveHeader>()
+
+        //マテリアルライブラリ(実行中に編集できる)
+        .init_resource::<material_library::MaterialLibrary>()
+        .add_systems( Update, material_library::edit_materials.run_if( app_state::in_viewer ) ) //[M]:選択 [Shift]+[M]:再読込 角かっこ:編集
+
+        //光源リグ(実行中に光源を追加・削除・調整できる)
+        .init_resource::<light_rig::LightRig>()
+        .register_type::<light_rig::LightKind>()
+        .register_type::<light_rig::RigLight>()
+        .register_type::<light_rig::OrbitLight>()
+        .add_systems( Update, light_rig::edit_light_rig.run_if( app_state::in_viewer ) ) //[PageUp]/[PageDown]:選択 [Insert]/[Delete]:追加・削除 など
+
+        //地面の目盛りと座標軸
+        .init_resource::<reference_grid::ReferenceGrid>()
+
+        //カメラと光源のデバッグ表示
+        .init_resource::<debug_overlay::DebugOverlays>()
+
+        //部品の選択・非表示・単独表示・半透明
+        .init_resource::<part_visibility::PartControls>()
+        .add_systems
+        (   Update,
+            (   (   part_visibility::select_part, //[Shift]+クリック:選択 [U]:親を選択
+                    part_visibility::hide_parts,  //[H]:隠す [I]:単独表示 [Alt]+[H]:全部表示
+                    part_visibility::xray_parts,  //[T]:半透明 [Alt]+[T]:全部戻す
+                )
+                .chain()
+                .after( picking::detect_clicks )
+                .run_if( app_state::in_viewer ),
+                part_visibility::refresh_part_status, //HUDに表示する状態
+                part_visibility::draw_selected_part,  //選んだ部品の枠
+            )
+            .chain()
+        )
+
+        //表示モード(ワイヤーフレーム・頂点法線・ライティングなし)
+        .init_resource::<render_mode::RenderModes>()
+        .add_systems
+        (   Update,
+            (   render_mode::cycle_render_mode.run_if( app_state::in_viewer ), //[R]:次のモード [Shift]+[R]:前のモード
+                render_mode::apply_render_mode,   //マテリアルの差し替え
+                render_mode::draw_vertex_normals, //頂点法線の線
+            )
+            .chain()
+        )
+
+        //スカイボックスと環境マップライティング(3Dカメラのviewportの中だけに描かれる)
+        .init_resource::<skybox::Sky>()
+        .add_systems
+        (   Update,
+            (   skybox::toggle_skybox.run_if( app_state::in_viewer ), //[O]:ON/OFF [Shift]+[O]:再読込
+                skybox::prepare_skybox, //読み込めた画像をキューブマップにする
+                skybox::apply_skybox,   //3Dカメラへ反映
+            )
+            .chain()
+        )
+
+        //昼夜の移り変わり(太陽を動かし、光と空の色を時刻に合わせる)
+        .init_resource::<day_night::DayNight>()
+        .register_type::<day_night::DayNight>()
+        .add_systems
+        (   Update,
+            (   day_night::operate_day_night, //[J]:有効・無効 [8]/[9]:時間の進みの速さ
+                day_night::advance_day_night, //時刻を進める
+            )
+            .chain()
+            .before( light_rig::move_orbit_lights ) //太陽の向きを同じフレームで反映する
+            .run_if( app_state::in_viewer )
+        )
+
+        //拾った鍵・HUDのメッセージ・インベントリ
+        .init_resource::<lock_key::KeyRing>()
+        .init_resource::<hud_message::HudMessage>()
+        .init_resource::<inventory::Inventory>()
+
+        //クリックと宝箱の上蓋のイベント
+        .add_event::<picking::MeshClicked>()
+        .add_event::<chest_lid::ToggleLid>()
+        .add_event::<chest_lid::LidOpened>()
+        .add_event::<chest_lid::LidClosed>()
+
+        //鍵と宝箱の上蓋の開閉
+        .add_systems
+        (   Update,
+            (   picking::detect_clicks              //クリックされたメッシュを探す(一時停止中はしない)
+                    .run_if( in_state( app_state::AppState::Viewer ) ),
+                lock_key::pick_up_keys,             //クリックで鍵を拾う
+                lock_key::unlock_by_click,          //クリックで錠前を開ける
+                chest_lid::toggle_lid_by_key        //[Space]で開閉(一時停止中はしない)
+                    .run_if( in_state( app_state::AppState::Viewer ) ),
+                chest_lid::toggle_lid_by_click,     //クリックで開閉
+                chest_lid::apply_toggle_lid,        //開閉の切換
+                chest_lid::animate_lids,            //上蓋を動かす
+                loot::roll_loot,                    //開いた宝箱の中身を抽選
+                treasure_hunt::find_hunt_chests,    //宝探し:注目点が近づいた宝箱を発見
+                treasure_hunt::count_opened_chests, //宝探し:開けた宝箱を数える
+            )
+            .chain() //実行順を固定
+            .run_if( app_state::in_viewer ) //一時停止中は時間が止まる(コマ送りで動く)
+        )
+
+        //インベントリ
+        .add_systems
+        (   Update,
+            (   inventory::operate_inventory, //[Tab]:表示の切換 [F8]:保存 [Shift]+[F8]:読込
+                inventory::show_inventory,    //パネルの表示
             )
+            .run_if( app_state::in_viewer )
         )
 
-        //メインルーチンを登録する
+        //メインルーチンを登録する(カメラはビューアと一時停止中だけ動かす)
         .add_systems
         (   Update,
             (   (   (   catch_input::from_keyboard, //極座標を更新(キー入力)
                         catch_input::from_mouse,    //極座標を更新(マウス)
                     ),
-                    move_orbit_camera,              //極座標カメラを移動
+                    model_viewer::fit_model,              //モデルの大きさにカメラを合わせる
+                    camera_path::play_camera_path,        //カメラパスを再生
+                    camera_follow::follow_camera_target,  //注目点をターゲットへ追従
+                    camera_history::step_history,         //視点履歴のundo/redo
+                    camera_history::record_settled_orbit, //落ち着いた視点を履歴へ記録
+                    move_orbit_camera,                    //極座標カメラを移動
+                    light_rig::move_orbit_lights,         //極座標の光源を移動([L]+左ドラッグ)
                 )
-                .chain(), //実行順を固定
+                .chain() //実行順を固定
+                .run_if( app_state::in_viewer ),
 
-                bevy::window::close_on_esc, //[ESC]キーで終了
-                toggle_window_mode,         //ウィンドウとフルスクリーンの切換
-                show_parameter,             //情報を表示
-                show_gizmos,                //ギズモの表示
+                (   camera_path::edit_camera_path,         //カメラパスの記録と再生の操作
+                    camera_follow::toggle_follow,          //追従カメラのON/OFF
+                    catch_input::toggle_zoom_mode,         //ズームのモードの切換
+                    reference_grid::toggle_reference_grid, //地面の目盛りと座標軸の表示の切換
+                    debug_overlay::toggle_debug_overlays,  //[Ctrl]+[1]～[6]:デバッグ表示の切換
+                    show_parameter,                        //情報を表示
+                )
+                .run_if( app_state::in_viewer ),
+
+                toggle_window_mode,                  //ウィンドウとフルスクリーンの切換
+                show_gizmos,                         //ギズモの表示
+                reference_grid::draw_reference_grid, //地面の目盛りと座標軸
+                debug_overlay::draw_debug_overlays,  //カメラと光源のデバッグ表示
             )
         )
 
@@ -67,7 +345,7 @@ fn main()
 //------------------------------------------------------------------------------
 
 //極座標の型
-#[derive( Clone, Copy )]
+#[derive( Clone, Copy, PartialEq, Serialize, Deserialize, Reflect )]
 struct Orbit
 {   r    : f32, //極座標のr（注目点からカメラまでの距離）
     theta: f32, //極座標のΘ（注目点から見たカメラの垂直角度）
@@ -82,13 +360,56 @@ impl Orbit
         let z = self.r * self.theta.sin() * self.phi.cos();
         Vec3::new( x, y, z )
     }
+
+    //直交座標から極座標へ変換する(into_vec3の逆)
+    fn from_vec3( vec3: Vec3 ) -> Self
+    {   let r = vec3.length();
+        let theta = if r > 0.0 { ( -vec3.y / r ).clamp( -1.0, 1.0 ).acos() } else { 0.0 };
+        let phi = vec3.x.atan2( vec3.z ).rem_euclid( TAU );
+        Self { r, theta, phi }
+    }
+
+    //別の極座標との間を補間する(φは近い方向へ回る)
+    fn lerp( self, to: Self, t: f32 ) -> Self
+    {   let mut phi_delta = ( to.phi - self.phi ) % TAU;
+        phi_delta -= if phi_delta >   PI { TAU } else { 0.0 };
+        phi_delta += if phi_delta <= -PI { TAU } else { 0.0 };
+
+        Self
+        {   r    : self.r     + ( to.r     - self.r     ) * t,
+            theta: self.theta + ( to.theta - self.theta ) * t,
+            phi  : ( self.phi + phi_delta * t ).rem_euclid( TAU ),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//イーズイン・アウト(0.0～1.0の進み具合をなめらかにする)
+fn ease_in_out( t: f32 ) -> f32
+{   let t = t.clamp( 0.0, 1.0 );
+    t * t * ( 3.0 - 2.0 * t )
+}
+
+//2色を混ぜる(tが0.0ならa、1.0ならb)
+fn mix( a: Color, b: Color, t: f32 ) -> Color
+{   let t = t.clamp( 0.0, 1.0 );
+    let [ ar, ag, ab, aa ] = a.as_rgba_f32();
+    let [ br, bg, bb, ba ] = b.as_rgba_f32();
+    Color::rgba( ar + ( br - ar ) * t, ag + ( bg - ag ) * t, ab + ( bb - ab ) * t, aa + ( ba - aa ) * t )
 }
 
 //------------------------------------------------------------------------------
 
 //極座標カメラに付けるComponent
-#[derive( Component )]
-pub struct OrbitCamera { orbit: Orbit }
+#[derive( Component, Reflect )]
+#[reflect( Component )]
+pub struct OrbitCamera
+{   orbit: Orbit, //注目点から見たカメラの位置
+    focus: Vec3,  //注目点
+    min_r: f32,   //rの最小値(表示するモデルの大きさに合わせて変わる)
+    max_r: f32,   //rの最大値(同上)
+}
 
 //極座標カメラの初期位置
 impl Default for OrbitCamera
@@ -98,13 +419,17 @@ impl Default for OrbitCamera
             {   r    : ORBIT_CAMERA_INIT_R,
                 theta: ORBIT_CAMERA_INIT_THETA,
                 phi  : ORBIT_CAMERA_INIT_PHI,
-            }
+            },
+            focus: Vec3::ZERO,
+            min_r: ORBIT_CAMERA_MIN_R,
+            max_r: ORBIT_CAMERA_MAX_R,
         }
     }
 }
 
 //UIテキストに付けるComponent
-#[derive( Component )]
+#[derive( Component, Reflect )]
+#[reflect( Component )]
 struct DisplayBoard;
 
 //------------------------------------------------------------------------------
@@ -152,21 +477,102 @@ fn move_orbit_camera
 {   let Ok ( ( camera, mut transform ) ) = q_camera.get_single_mut() else { return };
 
     //カメラの位置と向きを更新する
-    let translation = camera.orbit.into_vec3();
+    let translation = camera.focus + camera.orbit.into_vec3();
     *transform = Transform::from_translation( translation )
-        .looking_at( Vec3::ZERO, Vec3::Y );
+        .looking_at( camera.focus, Vec3::Y );
 }
 
 //------------------------------------------------------------------------------
 
-//極座標の情報を表示する
+//HUDに表示する各機能の状態
+//(システムの引数の数には上限があるので、機能が増えてもここへまとめる)
+#[derive( SystemParam )]
+struct HudStatus<'w, 's>
+{   model_info    : Option<Res<'w, model_viewer::ModelInfo>>,
+    path          : Res<'w, camera_path::CameraPath>,
+    player        : Res<'w, camera_path::PathPlayer>,
+    key_ring      : Res<'w, lock_key::KeyRing>,
+    hunt          : Option<Res<'w, treasure_hunt::HuntRound>>,
+    library       : Res<'w, material_library::MaterialLibrary>,
+    materials     : Res<'w, Assets<StandardMaterial>>,
+    light_rig     : Res<'w, light_rig::LightRig>,
+    q_lights      : Query<'w, 's, ( &'static Name, &'static light_rig::RigLight, Option<&'static DirectionalLight>, Option<&'static PointLight>, Option<&'static SpotLight> )>,
+    q_orbit_lights: Query<'w, 's, ( Entity, &'static light_rig::OrbitLight )>,
+    ambient       : Res<'w, AmbientLight>,
+    day_night     : Res<'w, day_night::DayNight>,
+    parts         : Res<'w, part_visibility::PartControls>,
+    message       : Res<'w, hud_message::HudMessage>,
+    time          : Res<'w, Time>,
+}
+
+impl HudStatus<'_, '_>
+{   //機能ごとの状態の行(表示するものが無い機能は飛ばす)
+    fn lines( &self ) -> Vec<String>
+    {   //[L]+左ドラッグで回す光源の極座標の情報
+        let orbit_light = light_rig::orbit_light_target( &self.light_rig, self.q_orbit_lights.iter().map( | ( entity, _ ) | entity ) )
+            .and_then( | entity | self.q_orbit_lights.get( entity ).ok() );
+        let orbit_light_info = orbit_light.map( | ( _, light ) |
+        {   let theta = light.orbit.theta.to_degrees();
+            let phi   = light.orbit.phi.to_degrees();
+            format!( " light theta:{theta:06.02}\n light phi:{phi:06.02}" )
+        });
+
+        //glTFモデルの情報(モデルを表示している時だけ)
+        let model_info = self.model_info.as_ref().map( | info |
+            format!
+            (   " model:{}\n meshes:{} materials:{}\n vertices:{}",
+                info.path, info.meshes, info.materials, info.vertices,
+            )
+        );
+
+        //カメラパスの情報(キーフレームがある時だけ)
+        let path_info = ( ! self.path.keyframes.is_empty() ).then( || self.player.status( &self.path ) );
+
+        //時間の速さ(一時停止中は表示する)
+        let paused = if self.time.is_paused() { " paused" } else { "" };
+        let time_info = Some ( format!( " time:x{}{paused}", self.time.relative_speed() ) );
+
+        //持っている鍵
+        let key_info = ( ! self.key_ring.key_ids.is_empty() )
+            .then( || format!( " keys:{}", self.key_ring.key_ids.join( "," ) ) );
+
+        //宝探しの得点と時間(宝探しモードの時だけ)
+        let hunt_info = self.hunt.as_ref().map( | hunt | hunt.status() );
+
+        //編集中のマテリアル([M]で選んだ時だけ)
+        let material_info = self.library.status( &self.materials );
+
+        //編集中の光源([PageUp]/[PageDown]で選んだ時だけ)
+        let light_info = light_rig::status( &self.light_rig, &self.q_lights, &self.ambient );
+
+        //昼夜の移り変わりの時計([J]で有効にした時だけ)
+        let clock_info = self.day_night.status();
+
+        //選んだ部品と、隠した部品・半透明の部品の数
+        let part_info = self.parts.status().map( str::to_string );
+
+        //一定時間だけ表示するメッセージ
+        let message = self.message.text( &self.time ).map( | text | format!( " {text}" ) );
+
+        [   orbit_light_info, model_info, path_info, time_info, key_info, hunt_info,
+            material_info, light_info, clock_info, part_info, message,
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+//極座標の情報と各機能の状態を表示する
 fn show_parameter
 (   mut q_text: Query<&mut Text, With<DisplayBoard>>,
-    q_camera: Query<&OrbitCamera>,
+    q_camera: Query<( &OrbitCamera, &Projection )>,
     q_window: Query<&Window>,
+    zoom_mode: Res<catch_input::ZoomMode>,
+    status: HudStatus,
 )
 {   let Ok ( mut text ) = q_text.get_single_mut() else { return };
-    let Ok ( camera ) = q_camera.get_single() else { return };
+    let Ok ( ( camera, projection ) ) = q_camera.get_single() else { return };
     let orbit = &camera.orbit;
     let Ok( window ) = q_window.get_single() else { return };
 
@@ -176,6 +582,13 @@ fn show_parameter
     let phi   = orbit.phi.to_degrees();   //ラジアンから度へ変換
     let info  = format!( " r:{r:3.02}\n theta:{theta:06.02}\n phi:{phi:06.02}" );
 
+    //視野角の情報
+    let fov = match projection
+    {   Projection::Perspective ( perspective ) => perspective.fov.to_degrees(),
+        Projection::Orthographic ( _ ) => 0.0,
+    };
+    let fov_info = format!( "\n fov:{fov:05.02} ({})", zoom_mode.name() );
+
     //ウィンドウの解像度の情報
     let whs = format!
     (   "\n width:{}\n height:{}\n scale:{}",
@@ -184,6 +597,9 @@ fn show_parameter
         window.scale_factor(),
     );
 
+    //各機能の状態
+    let lines: String = status.lines().into_iter().map( | line | format!( "\n{line}" ) ).collect();
+
     //表示の更新
-    text.sections[ 0 ].value = format!( "{info}{whs}" );
+    text.sections[ 0 ].value = format!( "{info}{fov_info}{whs}{lines}" );
 }
\ No newline at end of file
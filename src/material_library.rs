@@ -0,0 +1,206 @@
+use super::*;
+
+use std::collections::{ BTreeMap, HashMap };
+use scene_file::MaterialDesc;
+use procedural_texture::TextureDesc;
+
+//------------------------------------------------------------------------------
+
+//名前付きのPBRマテリアルの定義(materials.ronの1項目)
+#[derive( Clone, Deserialize )]
+#[serde( default, deny_unknown_fields )]
+pub struct MaterialSpec
+{   pub base_color: MaterialDesc,           //基本色(名前付きマテリアルは使えない)
+    pub metallic  : f32,                    //金属っぽさ(0.0～1.0)
+    pub roughness : f32,                    //表面の粗さ(0.0～1.0)
+    pub base_color_texture: Option<TextureSource>, //基本色のテクスチャ
+    pub normal_map_texture: Option<String>,        //法線マップのテクスチャ(assetsフォルダからの相対パス)
+}
+
+//テクスチャの出どころ
+#[derive( Clone, Deserialize )]
+pub enum TextureSource
+{   File       ( String ),      //画像ファイル(assetsフォルダからの相対パス)
+    Procedural ( TextureDesc ), //プロシージャルテクスチャ
+}
+
+impl Default for MaterialSpec
+{   fn default() -> Self
+    {   Self
+        {   base_color: MaterialDesc::Rgb( 1.0, 1.0, 1.0 ),
+            metallic  : 0.0,
+            roughness : 0.5,
+            base_color_texture: None,
+            normal_map_texture: None,
+        }
+    }
+}
+
+impl MaterialSpec
+{   //値をチェックする
+    pub fn validate( &self ) -> Result<(), String>
+    {   self.base_color.to_color()?;
+        if let Some ( TextureSource::Procedural ( desc ) ) = &self.base_color_texture { desc.validate()?; }
+        for value in [ self.metallic, self.roughness ]
+        {   if ! ( 0.0..=1.0 ).contains( &value )
+            {   return Err ( "metallic and roughness must be between 0.0 and 1.0".to_string() );
+            }
+        }
+        Ok (())
+    }
+
+    //StandardMaterialを作る(ファイルのテクスチャは非同期に読み込まれる)
+    fn to_material( &self, asset_server: &AssetServer, images: &mut Assets<Image> ) -> StandardMaterial
+    {   let base_color_texture = self.base_color_texture.as_ref().map( | source |
+            match source
+            {   TextureSource::File ( path ) => asset_server.load( path.as_str() ),
+                TextureSource::Procedural ( desc ) => images.add( desc.to_image() ),
+            }
+        );
+        StandardMaterial
+        {   base_color: self.base_color.to_color().unwrap_or_default(),
+            metallic: self.metallic,
+            perceptual_roughness: self.roughness,
+            base_color_texture,
+            normal_map_texture: self.normal_map_texture.as_ref().map( | path | asset_server.load( path.as_str() ) ),
+            ..default()
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//マテリアルのライブラリ(同じマテリアルは一度だけ作って共有する)
+#[derive( Resource, Default )]
+pub struct MaterialLibrary
+{   named : BTreeMap<String, Handle<StandardMaterial>>,    //名前付きのマテリアル(名前順)
+    colors: HashMap<[ u32; 4 ], Handle<StandardMaterial>>, //単色のマテリアル(RGBAのビット列で引く)
+    textured: HashMap<String, Handle<StandardMaterial>>,   //プロシージャルテクスチャのマテリアル(設定の文字列で引く)
+    pub selected: Option<String>,                          //実行中に編集しているマテリアルの名前
+}
+
+impl MaterialLibrary
+{   //定義ファイルを読み込んでマテリアルを作る(作成済みのものは中身だけ差し替える)
+    pub fn load
+    (   &mut self,
+        asset_server: &AssetServer,
+        materials: &mut Assets<StandardMaterial>,
+        images: &mut Assets<Image>,
+    )
+    {   let file = ron_file::asset_path( MATERIALS_FILE );
+        let specs = match ron_file::load::<HashMap<String, MaterialSpec>>( &file )
+        {   Ok  ( specs ) => specs,
+            Err ( err   ) => { error!( "failed to load materials: {err}" ); return }
+        };
+
+        for ( name, spec ) in specs
+        {   if let Err ( message ) = spec.validate()
+            {   error!( "{}: {name}: {message}", file.display() );
+                continue
+            }
+            let material = spec.to_material( asset_server, images );
+            match self.named.get( &name ).and_then( | handle | materials.get_mut( handle ) )
+            {   Some ( current ) => *current = material,
+                None => { self.named.insert( name, materials.add( material ) ); }
+            }
+        }
+    }
+
+    //マテリアルのハンドル(名前が見つからなければ白の単色にする)
+    pub fn handle
+    (   &mut self,
+        desc: &MaterialDesc,
+        materials: &mut Assets<StandardMaterial>,
+        images: &mut Assets<Image>,
+    ) -> Handle<StandardMaterial>
+    {   let color = match desc
+        {   MaterialDesc::Named ( name ) =>
+            {   if let Some ( handle ) = self.named.get( name ) { return handle.clone() }
+                error!( "unknown material {name:?}" );
+                Color::WHITE
+            }
+            MaterialDesc::Procedural ( texture ) =>
+            {   let key = format!( "{texture:?}" );
+                let handle = self.textured.entry( key ).or_insert_with( ||
+                {   let base_color_texture = Some ( images.add( texture.to_image() ) );
+                    materials.add( StandardMaterial { base_color_texture, ..default() } )
+                });
+                return handle.clone()
+            }
+            _ => desc.to_color().unwrap_or_default(),
+        };
+        self.color( color, materials )
+    }
+
+    //単色のマテリアルのハンドル
+    pub fn color
+    (   &mut self,
+        color: Color,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Handle<StandardMaterial>
+    {   let key = color.as_rgba_f32().map( f32::to_bits );
+        self.colors.entry( key ).or_insert_with( || materials.add( color.into() ) ).clone()
+    }
+
+    //編集中のマテリアルのハンドル
+    fn selected_handle( &self ) -> Option<&Handle<StandardMaterial>>
+    {   self.selected.as_ref().and_then( | name | self.named.get( name ) )
+    }
+
+    //HUDに表示する編集中のマテリアルの状態
+    pub fn status( &self, materials: &Assets<StandardMaterial> ) -> Option<String>
+    {   let name = self.selected.as_ref()?;
+        let material = materials.get( self.selected_handle()? )?;
+        Some ( format!
+        (   " material:{name}\n metallic:{:.02} roughness:{:.02}",
+            material.metallic, material.perceptual_roughness,
+        ))
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//起動時にマテリアルを作る
+pub fn setup_materials
+(   mut library: ResMut<MaterialLibrary>,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+)
+{   library.load( &asset_server, &mut materials, &mut images );
+}
+
+//実行中にマテリアルを編集する(同じマテリアルを使っている部品は全部変わる)
+//  [M]:編集するマテリアルの切換 [Shift]+[M]:定義ファイルの再読込
+//  [BracketLeft]/[BracketRight]:roughnessを増減 [Shift]+同じキー:metallicを増減
+pub fn edit_materials
+(   inkey: Res<Input<KeyCode>>,
+    mut library: ResMut<MaterialLibrary>,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+)
+{   let is_shift = inkey.any_pressed( [ KeyCode::ShiftLeft, KeyCode::ShiftRight ] );
+
+    if inkey.just_pressed( KeyCode::M )
+    {   if is_shift
+        {   library.load( &asset_server, &mut materials, &mut images );
+            info!( "materials reloaded" );
+        }
+        else
+        {   //名前順に次のマテリアルへ(最後の次は編集をやめる)
+            let next = library.named.keys()
+                .find( | name | library.selected.as_ref().map_or( true, | selected | *name > selected ) )
+                .cloned();
+            library.selected = next;
+        }
+    }
+
+    let step = if inkey.just_pressed( KeyCode::BracketRight ) { MATERIAL_EDIT_STEP }
+        else if inkey.just_pressed( KeyCode::BracketLeft ) { -MATERIAL_EDIT_STEP }
+        else { return };
+    let Some ( handle ) = library.selected_handle() else { return };
+    let Some ( material ) = materials.get_mut( handle ) else { return };
+    let value = if is_shift { &mut material.metallic } else { &mut material.perceptual_roughness };
+    *value = ( *value + step ).clamp( 0.0, 1.0 );
+}
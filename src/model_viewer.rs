@@ -0,0 +1,107 @@
+use super::*;
+
+use bevy::{ render::primitives::Aabb, utils::HashSet };
+use app_state::{ SceneList, SceneEntity, ViewerScene };
+
+//------------------------------------------------------------------------------
+
+//glTFモデルのルートに付けるComponent
+#[derive( Component, Default )]
+pub struct ViewerModel { is_fitted: bool }
+
+//HUDに表示するモデルの情報
+#[derive( Resource, Default )]
+pub struct ModelInfo
+{   pub path     : String,
+    pub meshes   : usize, //メッシュの種類の数
+    pub materials: usize, //マテリアルの種類の数
+    pub vertices : usize, //全メッシュの頂点数の合計
+    pub radius   : f32,   //バウンディング球の半径
+}
+
+//------------------------------------------------------------------------------
+
+//選んだglTFモデルを読み込む
+pub fn spawn_model
+(   list: Res<SceneList>,
+    asset_server: Res<AssetServer>,
+    mut cmds: Commands,
+)
+{   let Some ( ViewerScene::Model ( path ) ) = list.selected() else { return };
+
+    let scene = asset_server.load( format!( "{path}#Scene0" ) );
+    cmds.spawn( ( SceneBundle { scene, ..default() }, ViewerModel::default() ) )
+        .insert( ( Name::new( "model" ), SceneEntity ) );
+    cmds.insert_resource( ModelInfo { path: path.clone(), ..default() } );
+}
+
+//------------------------------------------------------------------------------
+
+//モデルが読み込まれたら注目点へ置き、大きさに合わせてカメラの距離と範囲を決める
+pub fn fit_model
+(   mut q_model: Query<( Entity, &mut ViewerModel, &mut Transform )>,
+    q_children: Query<&Children>,
+    q_meshes: Query<( &Handle<Mesh>, Option<&Handle<StandardMaterial>>, Option<&Aabb>, &GlobalTransform )>,
+    mut q_camera: Query<( &mut OrbitCamera, &Projection )>,
+    meshes: Res<Assets<Mesh>>,
+    info: Option<ResMut<ModelInfo>>,
+)
+{   let Ok ( ( entity, mut model, mut transform ) ) = q_model.get_single_mut() else { return };
+    if model.is_fitted { return }
+    let Some ( mut info ) = info else { return };
+    let Ok ( ( mut camera, projection ) ) = q_camera.get_single_mut() else { return };
+
+    //メッシュのAABBをワールド座標でまとめる
+    let mut min = Vec3::splat( f32::MAX );
+    let mut max = Vec3::splat( f32::MIN );
+    let mut mesh_ids = HashSet::new();
+    let mut material_ids = HashSet::new();
+    let mut vertices = 0;
+    for descendant in q_children.iter_descendants( entity )
+    {   let Ok ( ( mesh, material, aabb, global ) ) = q_meshes.get( descendant ) else { continue };
+
+        //AABBがまだ計算されていないメッシュがあれば次のフレームまで待つ
+        let Some ( aabb ) = aabb else { return };
+
+        let ( aabb_min, aabb_max ) = ( Vec3::from( aabb.min() ), Vec3::from( aabb.max() ) );
+        for i in 0..8
+        {   let corner = Vec3::new
+            (   if i & 1 == 0 { aabb_min.x } else { aabb_max.x },
+                if i & 2 == 0 { aabb_min.y } else { aabb_max.y },
+                if i & 4 == 0 { aabb_min.z } else { aabb_max.z },
+            );
+            let point = global.transform_point( corner );
+            min = min.min( point );
+            max = max.max( point );
+        }
+
+        mesh_ids.insert( mesh.id() );
+        if let Some ( material ) = material { material_ids.insert( material.id() ); }
+        vertices += meshes.get( mesh ).map_or( 0, | mesh | mesh.count_vertices() );
+    }
+
+    //シーンがまだspawnされていないなら
+    if mesh_ids.is_empty() { return }
+    model.is_fitted = true;
+
+    //モデルの中心を注目点へ合わせる
+    let center = ( min + max ) * 0.5;
+    let radius = ( ( max - min ).length() * 0.5 ).max( f32::EPSILON );
+    transform.translation += camera.focus - center;
+
+    //バウンディング球がちょうど画面に収まる距離を基準にrとその範囲を決める
+    let fov = match projection
+    {   Projection::Perspective ( perspective ) => perspective.fov,
+        Projection::Orthographic ( _ ) => CAMERA_INIT_FOV,
+    };
+    let distance = radius / ( fov * 0.5 ).sin();
+    camera.min_r = radius * MODEL_MIN_R_COEF;
+    camera.max_r = distance * MODEL_MAX_R_COEF;
+    camera.orbit.r = ( distance * MODEL_INIT_R_COEF ).clamp( camera.min_r, camera.max_r );
+
+    //HUD用の情報
+    info.meshes    = mesh_ids.len();
+    info.materials = material_ids.len();
+    info.vertices  = vertices;
+    info.radius    = radius;
+}
@@ -0,0 +1,257 @@
+use super::*;
+
+use std::collections::HashMap;
+use bevy::render::primitives::Aabb;
+use hud_message::HudMessage;
+use picking::MeshClicked;
+use render_mode::BaseMaterial;
+
+//------------------------------------------------------------------------------
+
+//非表示・単独表示で変えたエンティティに付けて、元のVisibilityを覚えておくComponent
+#[derive( Component )]
+pub struct HiddenPart ( Visibility );
+
+//半透明にしたエンティティに付けて、元のマテリアルを覚えておくComponent
+#[derive( Component )]
+pub struct XRayPart ( Handle<StandardMaterial> );
+
+//部品の選択と表示の状態
+#[derive( Resource, Default )]
+pub struct PartControls
+{   pub selected: Option<Entity>, //[Shift]+クリックで選んだ部品
+    is_isolated: bool,            //選んだ部品だけ表示しているか
+    xray_materials: HashMap<Handle<StandardMaterial>, Handle<StandardMaterial>>, //元のマテリアル→半透明のマテリアル
+    status: String,               //HUDに表示する状態
+}
+
+impl PartControls
+{   //HUDに表示する部品の状態(何も選んでおらず、隠した部品も半透明の部品も無ければNone)
+    pub fn status( &self ) -> Option<&str>
+    {   ( ! self.status.is_empty() ).then_some( self.status.as_str() )
+    }
+
+    //半透明のマテリアル(マテリアルは共有されているので、元のマテリアルは変えずに複製する)
+    fn xray_variant
+    (   &mut self,
+        base: &Handle<StandardMaterial>,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Option<Handle<StandardMaterial>>
+    {   if let Some ( xray ) = self.xray_materials.get( base ) { return Some ( xray.clone() ) }
+
+        let mut xray = materials.get( base )?.clone();
+        let alpha = xray.base_color.a() * PART_XRAY_ALPHA;
+        xray.base_color.set_a( alpha );
+        xray.alpha_mode = AlphaMode::Blend;
+        let xray = materials.add( xray );
+        self.xray_materials.insert( base.clone(), xray.clone() );
+        Some ( xray )
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//[Shift]+クリックで部品を選ぶ(選んでいる部品をもう一度選ぶと選択を外す)
+//  [U]:選んでいる部品の親を選ぶ
+pub fn select_part
+(   mut parts: ResMut<PartControls>,
+    q_parent: Query<&Parent>,
+    mut e_clicked: EventReader<MeshClicked>,
+    inkey: Res<Input<KeyCode>>,
+)
+{   for clicked in e_clicked.iter()
+    {   if ! clicked.is_shift { continue }
+        parts.selected = ( parts.selected != Some ( clicked.entity ) ).then_some( clicked.entity );
+    }
+
+    if inkey.just_pressed( KeyCode::U )
+    {   if let Some ( parent ) = parts.selected.and_then( | entity | q_parent.get( entity ).ok() )
+        {   parts.selected = Some ( parent.get() );
+        }
+    }
+}
+
+//部品の表示の切換
+//  [H]:選んだ部品を隠す [I]:選んだ部品だけ表示する [Alt]+[H]:全部表示する
+pub fn hide_parts
+(   mut parts: ResMut<PartControls>,
+    mut q_visibility: Query<( &mut Visibility, Option<&HiddenPart> )>,
+    q_meshes: Query<Entity, With<Handle<Mesh>>>,
+    q_hidden: Query<Entity, With<HiddenPart>>,
+    q_parent: Query<&Parent>,
+    inkey: Res<Input<KeyCode>>,
+    mut message: ResMut<HudMessage>,
+    time: Res<Time>,
+    mut cmds: Commands,
+)
+{   let is_hide    = inkey.just_pressed( KeyCode::H );
+    let is_isolate = inkey.just_pressed( KeyCode::I );
+    if ! is_hide && ! is_isolate { return }
+
+    //全部表示する
+    if is_hide && inkey.any_pressed( [ KeyCode::AltLeft, KeyCode::AltRight ] )
+    {   for entity in q_hidden.iter()
+        {   restore_visibility( entity, &mut q_visibility, &mut cmds );
+        }
+        parts.is_isolated = false;
+        message.show( "All parts shown", &time );
+        return
+    }
+
+    let Some ( selected ) = parts.selected.filter( | entity | q_visibility.contains( *entity ) ) else
+    {   message.show( "Shift+click a part first", &time );
+        return
+    };
+
+    //選んだ部品を隠す(隠した部品はクリックできないので選択を外す)
+    if is_hide
+    {   set_visibility( selected, Visibility::Hidden, &mut q_visibility, &mut cmds );
+        parts.selected = None;
+        return
+    }
+
+    //選んだ部品とその子孫以外のメッシュを隠し、選んだ部品は親が隠れていても表示する
+    let is_selected = | entity: Entity | entity == selected;
+    let others: Vec<Entity> = q_meshes.iter()
+        .filter( | entity | picking::find_ancestor( *entity, &q_parent, is_selected ).is_none() )
+        .collect();
+    for entity in q_hidden.iter()
+    {   if entity != selected && ! others.contains( &entity )
+        {   restore_visibility( entity, &mut q_visibility, &mut cmds );
+        }
+    }
+    for entity in others
+    {   set_visibility( entity, Visibility::Hidden, &mut q_visibility, &mut cmds );
+    }
+    set_visibility( selected, Visibility::Visible, &mut q_visibility, &mut cmds );
+    parts.is_isolated = true;
+}
+
+//部品の半透明の切換
+//  [T]:選んだ部品を半透明にする・戻す [Alt]+[T]:全部戻す
+//  (部品自身にマテリアルが無ければ、子孫のメッシュを全部切り換える)
+pub fn xray_parts
+(   mut parts: ResMut<PartControls>,
+    mut q_materials: Query<( &mut Handle<StandardMaterial>, Option<&XRayPart>, Option<&mut BaseMaterial> )>,
+    q_xray: Query<Entity, With<XRayPart>>,
+    q_children: Query<&Children>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    inkey: Res<Input<KeyCode>>,
+    mut message: ResMut<HudMessage>,
+    time: Res<Time>,
+    mut cmds: Commands,
+)
+{   if ! inkey.just_pressed( KeyCode::T ) { return }
+
+    //切り換える部品と、半透明から戻すのか
+    let ( targets, is_restore ): ( Vec<Entity>, bool ) =
+        if inkey.any_pressed( [ KeyCode::AltLeft, KeyCode::AltRight ] )
+        {   ( q_xray.iter().collect(), true )
+        }
+        else
+        {   let Some ( selected ) = parts.selected else
+            {   message.show( "Shift+click a part first", &time );
+                return
+            };
+            let targets: Vec<Entity> = if q_materials.contains( selected ) { vec![ selected ] } else
+            {   q_children.iter_descendants( selected ).filter( | entity | q_materials.contains( *entity ) ).collect()
+            };
+            let is_restore = targets.iter().any( | entity | q_xray.contains( *entity ) );
+            ( targets, is_restore )
+        };
+
+    for entity in targets
+    {   let Ok ( ( mut handle, xray, base ) ) = q_materials.get_mut( entity ) else { continue };
+
+        //表示モードでマテリアルを差し替えている時は、差し替える前のマテリアルを切り換える
+        let current = base.as_ref().map_or( handle.clone(), | base | base.0.clone() );
+        let next = match ( is_restore, xray )
+        {   ( true, Some ( xray ) ) =>
+            {   cmds.entity( entity ).remove::<XRayPart>();
+                xray.0.clone()
+            }
+            ( false, None ) =>
+            {   let Some ( next ) = parts.xray_variant( &current, &mut materials ) else { continue };
+                cmds.entity( entity ).insert( XRayPart ( current ) );
+                next
+            }
+            _ => continue,
+        };
+        match base
+        {   Some ( mut base ) => base.0 = next,
+            None => *handle = next,
+        }
+    }
+
+    let text = if is_restore { "X-ray off" } else { "X-ray on" };
+    message.show( text, &time );
+}
+
+//消えた部品の選択を外して、HUDに表示する状態を更新する
+pub fn refresh_part_status
+(   mut parts: ResMut<PartControls>,
+    q_entities: Query<Entity>,
+    q_names: Query<&Name>,
+    q_hidden: Query<(), With<HiddenPart>>,
+    q_xray: Query<(), With<XRayPart>>,
+)
+{   //シーンを作り直すと部品は消える
+    if parts.selected.is_some_and( | entity | ! q_entities.contains( entity ) ) { parts.selected = None }
+
+    let hidden = q_hidden.iter().count();
+    let xray   = q_xray.iter().count();
+    if hidden == 0 { parts.is_isolated = false }
+    if xray == 0 && ! parts.xray_materials.is_empty() { parts.xray_materials.clear() } //元のマテリアルを離す
+
+    let status = if parts.selected.is_none() && hidden == 0 && xray == 0 { String::new() } else
+    {   let name = parts.selected.map_or( "-".to_string(), | entity |
+            q_names.get( entity ).map_or( format!( "{entity:?}" ), | name | name.to_string() )
+        );
+        let isolated = if parts.is_isolated { " (isolated)" } else { "" };
+        format!( " part:{name}{isolated}\n hidden:{hidden} xray:{xray}" )
+    };
+    if parts.status != status { parts.status = status }
+}
+
+//選んだ部品をAABBの枠で示す(部品自身にメッシュが無ければ子孫のメッシュ)
+pub fn draw_selected_part
+(   mut gizmos: Gizmos,
+    parts: Res<PartControls>,
+    q_aabbs: Query<( &Aabb, &GlobalTransform )>,
+    q_children: Query<&Children>,
+)
+{   let Some ( selected ) = parts.selected else { return };
+
+    let targets: Vec<Entity> = if q_aabbs.contains( selected ) { vec![ selected ] } else
+    {   q_children.iter_descendants( selected ).collect()
+    };
+    for ( aabb, transform ) in q_aabbs.iter_many( targets )
+    {   let local = Transform::from_translation( aabb.center.into() ).with_scale( ( aabb.half_extents * 2.0 ).into() );
+        gizmos.cuboid( *transform * local, PART_SELECTED_COLOR );
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//元のVisibilityを覚えてから変える
+fn set_visibility
+(   entity: Entity,
+    visibility: Visibility,
+    q_visibility: &mut Query<( &mut Visibility, Option<&HiddenPart> )>,
+    cmds: &mut Commands,
+)
+{   let Ok ( ( mut current, hidden ) ) = q_visibility.get_mut( entity ) else { return };
+    if hidden.is_none() { cmds.entity( entity ).insert( HiddenPart ( *current ) ); }
+    *current = visibility;
+}
+
+//覚えておいたVisibilityへ戻す
+fn restore_visibility
+(   entity: Entity,
+    q_visibility: &mut Query<( &mut Visibility, Option<&HiddenPart> )>,
+    cmds: &mut Commands,
+)
+{   let Ok ( ( mut current, Some ( hidden ) ) ) = q_visibility.get_mut( entity ) else { return };
+    *current = hidden.0;
+    cmds.entity( entity ).remove::<HiddenPart>();
+}
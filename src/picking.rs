@@ -0,0 +1,139 @@
+use super::*;
+
+use bevy::render::primitives::Aabb;
+
+//------------------------------------------------------------------------------
+
+//3Dオブジェクトがクリックされた時のイベント
+#[derive( Event, Clone, Copy )]
+pub struct MeshClicked
+{   pub entity  : Entity, //クリックされたメッシュのエンティティ
+    pub is_shift: bool,   //[Shift]を押しながらクリックしたか
+}
+
+//------------------------------------------------------------------------------
+
+//マウスの左クリック(ドラッグではなく)を検出して、カーソルの下のメッシュを探す
+pub fn detect_clicks
+(   q_window: Query<&Window>,
+    q_camera: Query<( &Camera, &GlobalTransform ), With<OrbitCamera>>,
+    q_meshes: Query<( Entity, &Aabb, &GlobalTransform, &ComputedVisibility, Option<&part_visibility::XRayPart> ), With<Handle<Mesh>>>,
+    mouse_button: Res<Input<MouseButton>>,
+    inkey: Res<Input<KeyCode>>,
+    mut e_clicked: EventWriter<MeshClicked>,
+    mut pressed_at: Local<Option<Vec2>>, //左ボタンを押した時のカーソル位置(ローカル変数)
+)
+{   let Ok ( window ) = q_window.get_single() else { return };
+    let Some ( cursor ) = window.cursor_position() else { return };
+
+    //押した位置を覚えておく
+    if mouse_button.just_pressed( MouseButton::Left ) { *pressed_at = Some ( cursor ) }
+    if ! mouse_button.just_released( MouseButton::Left ) { return }
+
+    //カーソルが動いていたらドラッグ(カメラの回転)なのでクリックではない
+    let Some ( pressed_at ) = pressed_at.take() else { return };
+    if pressed_at.distance( cursor ) > CLICK_MAX_MOVE { return }
+
+    //カーソル位置からのレイ(viewportの左上が原点になるよう位置をずらす)
+    let Ok ( ( camera, camera_transform ) ) = q_camera.get_single() else { return };
+    let Some ( rect ) = camera.logical_viewport_rect() else { return };
+    if ! rect.contains( cursor ) { return }
+    let Some ( ray ) = camera.viewport_to_world( camera_transform, cursor - rect.min ) else { return };
+
+    //一番手前のメッシュ([Shift]+クリックの部品の選択では、半透明の部品を透かして奥の部品を選ぶ)
+    let is_shift = inkey.any_pressed( [ KeyCode::ShiftLeft, KeyCode::ShiftRight ] );
+    let hit = q_meshes.iter()
+        .filter( | ( _, _, _, visibility, _ ) | visibility.is_visible() )
+        .filter_map( | ( entity, aabb, transform, _, xray ) |
+            Some ( ( entity, is_shift && xray.is_some(), ray_aabb( ray, aabb, transform )? ) )
+        )
+        .min_by( | a, b | a.1.cmp( &b.1 ).then( a.2.total_cmp( &b.2 ) ) );
+    let Some ( ( entity, _, _ ) ) = hit else { return };
+
+    e_clicked.send( MeshClicked { entity, is_shift } );
+}
+
+//レイとAABBの交差判定(交差するならレイの原点からの距離を返す)
+pub fn ray_aabb( ray: Ray, aabb: &Aabb, transform: &GlobalTransform ) -> Option<f32>
+{   //レイをメッシュのローカル座標へ変換する(距離の比率は変わらない)
+    let inverse = transform.compute_matrix().inverse();
+    let origin = inverse.transform_point3( ray.origin );
+    let direction = inverse.transform_vector3( ray.direction );
+
+    //スラブ法
+    let min = Vec3::from( aabb.min() );
+    let max = Vec3::from( aabb.max() );
+    let t1 = ( min - origin ) / direction;
+    let t2 = ( max - origin ) / direction;
+    let t_near = t1.min( t2 ).max_element();
+    let t_far  = t1.max( t2 ).min_element();
+
+    ( t_near <= t_far && t_far >= 0.0 ).then_some( t_near.max( 0.0 ) )
+}
+
+//エンティティ自身か祖先のうち、条件に合う最初のエンティティを探す
+pub fn find_ancestor<F: Fn( Entity ) -> bool>
+(   entity: Entity,
+    q_parent: &Query<&Parent>,
+    is_target: F,
+) -> Option<Entity>
+{   std::iter::once( entity )
+        .chain( q_parent.iter_ancestors( entity ) )
+        .find( | entity | is_target( *entity ) )
+}
+
+//------------------------------------------------------------------------------
+
+#[cfg( test )]
+mod tests
+{   use super::*;
+
+    //原点を中心とした一辺2の立方体
+    fn unit_box() -> Aabb { Aabb::from_min_max( Vec3::NEG_ONE, Vec3::ONE ) }
+
+    fn ray( origin: Vec3, direction: Vec3 ) -> Ray { Ray { origin, direction } }
+
+    #[test]
+    fn hit_from_outside()
+    {   let hit = ray_aabb( ray( Vec3::new( 0.0, 0.0, -5.0 ), Vec3::Z ), &unit_box(), &GlobalTransform::IDENTITY );
+        assert_eq!( hit, Some ( 4.0 ) );
+    }
+
+    #[test]
+    fn miss_beside_the_box()
+    {   let hit = ray_aabb( ray( Vec3::new( 0.0, 3.0, -5.0 ), Vec3::Z ), &unit_box(), &GlobalTransform::IDENTITY );
+        assert_eq!( hit, None );
+    }
+
+    //レイの原点が箱の中なら距離は0
+    #[test]
+    fn origin_inside_the_box()
+    {   let hit = ray_aabb( ray( Vec3::new( 0.2, -0.3, 0.5 ), Vec3::X ), &unit_box(), &GlobalTransform::IDENTITY );
+        assert_eq!( hit, Some ( 0.0 ) );
+    }
+
+    //箱がレイの後ろにあれば当たらない
+    #[test]
+    fn box_behind_the_ray()
+    {   let hit = ray_aabb( ray( Vec3::new( 0.0, 0.0, 5.0 ), Vec3::Z ), &unit_box(), &GlobalTransform::IDENTITY );
+        assert_eq!( hit, None );
+    }
+
+    //面と平行なレイ(方向の成分が0)は、その軸の範囲の中なら当たり、外なら当たらない
+    #[test]
+    fn ray_parallel_to_a_face()
+    {   let inside = ray_aabb( ray( Vec3::new( -5.0, 0.5, 0.0 ), Vec3::X ), &unit_box(), &GlobalTransform::IDENTITY );
+        assert_eq!( inside, Some ( 4.0 ) );
+
+        let outside = ray_aabb( ray( Vec3::new( -5.0, 2.0, 0.0 ), Vec3::X ), &unit_box(), &GlobalTransform::IDENTITY );
+        assert_eq!( outside, None );
+    }
+
+    //拡縮・移動したメッシュでもワールド座標の距離を返す
+    #[test]
+    fn hit_transformed_box()
+    {   let transform = GlobalTransform::from( Transform::from_xyz( 10.0, 0.0, 0.0 ).with_scale( Vec3::splat( 2.0 ) ) );
+        let hit = ray_aabb( ray( Vec3::ZERO, Vec3::X ), &unit_box(), &transform ).unwrap();
+        assert!( ( hit - 8.0 ).abs() < 1e-4, "{hit}" );
+    }
+}
@@ -0,0 +1,218 @@
+use super::*;
+
+use bevy::render::
+{   render_resource::{ AddressMode, Extent3d, SamplerDescriptor, TextureDimension, TextureFormat },
+    texture::ImageSampler,
+};
+use scene_file::MaterialDesc;
+
+//------------------------------------------------------------------------------
+
+//プロシージャルテクスチャの模様
+#[derive( Clone, Copy, PartialEq, Debug, Deserialize )]
+pub enum TexturePattern
+{   WoodGrain,    //木目
+    BrushedMetal, //ヘアライン加工の金属
+    Grass,        //ノイズの草地
+    Checker,      //市松模様
+    UvDebug,      //UV確認用(Uが赤、Vが緑のグラデーションと格子)
+}
+
+//プロシージャルテクスチャの設定(同じ設定なら必ず同じ画像になる)
+#[derive( Clone, Debug, Deserialize )]
+#[serde( deny_unknown_fields )]
+pub struct TextureDesc
+{   pub pattern: TexturePattern,
+    #[serde( default )]
+    pub seed: u64,                 //ノイズのシード
+    #[serde( default = "default_scale" )]
+    pub scale: f32,                //画像の一辺あたりの模様の繰り返し数
+    #[serde( default )]
+    pub colors: Vec<MaterialDesc>, //模様の2色(空なら模様ごとのデフォルト)
+    #[serde( default = "default_size" )]
+    pub size: u32,                 //画像の一辺のピクセル数
+}
+
+fn default_scale() -> f32 { PROCEDURAL_TEXTURE_SCALE }
+fn default_size() -> u32 { PROCEDURAL_TEXTURE_SIZE }
+
+impl TextureDesc
+{   //値をチェックする
+    pub fn validate( &self ) -> Result<(), String>
+    {   if ! self.scale.is_finite() || self.scale < 1.0
+        {   return Err ( "texture scale must be 1.0 or more".to_string() );
+        }
+        if ! ( 1..=PROCEDURAL_TEXTURE_MAX_SIZE ).contains( &self.size )
+        {   return Err ( format!( "texture size must be between 1 and {PROCEDURAL_TEXTURE_MAX_SIZE}" ) );
+        }
+        if ! matches!( self.colors.len(), 0 | 2 )
+        {   return Err ( "texture colors must be empty or two colors".to_string() );
+        }
+        for color in &self.colors { color.to_color()?; }
+        Ok (())
+    }
+
+    //模様の2色
+    fn colors( &self ) -> ( Color, Color )
+    {   if let [ a, b ] = self.colors.as_slice()
+        {   return ( a.to_color().unwrap_or_default(), b.to_color().unwrap_or_default() )
+        }
+        match self.pattern
+        {   TexturePattern::WoodGrain    => ( Color::rgb( 0.55, 0.35, 0.17 ), Color::rgb( 0.3, 0.16, 0.06 ) ),
+            TexturePattern::BrushedMetal => ( Color::rgb( 0.75, 0.75, 0.78 ), Color::rgb( 0.45, 0.45, 0.48 ) ),
+            TexturePattern::Grass        => ( Color::rgb( 0.45, 0.7, 0.25 ), Color::rgb( 0.2, 0.4, 0.1 ) ),
+            TexturePattern::Checker      => ( Color::WHITE, Color::DARK_GRAY ),
+            TexturePattern::UvDebug      => ( Color::WHITE, Color::BLACK ),
+        }
+    }
+
+    //Imageを作る(端がつながるので繰り返して貼れる)
+    pub fn to_image( &self ) -> Image
+    {   let size = Extent3d { width: self.size, height: self.size, depth_or_array_layers: 1 };
+        let mut image = Image::new( size, TextureDimension::D2, self.pixels(), TextureFormat::Rgba8UnormSrgb );
+        image.sampler_descriptor = ImageSampler::Descriptor( SamplerDescriptor
+        {   address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::Repeat,
+            ..ImageSampler::linear_descriptor()
+        });
+        image
+    }
+
+    //ピクセルのRGBA8(sRGB)の並び
+    pub fn pixels( &self ) -> Vec<u8>
+    {   let ( color_a, color_b ) = self.colors();
+        let period = self.scale.round() as i32; //整数にすると端がつながる
+        let size = self.size as usize;
+
+        let mut data = Vec::with_capacity( size * size * 4 );
+        for y in 0..size
+        {   for x in 0..size
+            {   let u = ( x as f32 + 0.5 ) / size as f32;
+                let v = ( y as f32 + 0.5 ) / size as f32;
+                let grain = hash( x as i32, y as i32, self.seed ); //ピクセルごとの細かいばらつき
+
+                let color = match self.pattern
+                {   TexturePattern::WoodGrain =>
+                    {   //年輪をノイズで揺らし、濃い部分を細くする
+                        let warp = fbm( u * 4.0, v * 4.0, ( 4, 4 ), self.seed ) * 1.5;
+                        let ring = ( v * period as f32 + warp ).fract();
+                        let t = ring.powf( 3.0 ) * 0.8 + grain * 0.2;
+                        mix( color_a, color_b, t )
+                    }
+                    TexturePattern::BrushedMetal =>
+                    {   //Uの方向に長く伸ばしたノイズの筋
+                        let streak = fbm( u * 2.0, v * period as f32 * 8.0, ( 2, period * 8 ), self.seed );
+                        mix( color_a, color_b, streak * 0.7 + grain * 0.3 )
+                    }
+                    TexturePattern::Grass =>
+                    {   //まだらな濃淡に、明るい草の葉を散らす
+                        let patch = fbm( u * period as f32, v * period as f32, ( period, period ), self.seed );
+                        let color = mix( color_a, color_b, patch );
+                        if grain > 0.85 { mix( color, Color::WHITE, 0.15 ) } else { color }
+                    }
+                    TexturePattern::Checker =>
+                    {   let cell = ( u * period as f32 ) as i32 + ( v * period as f32 ) as i32;
+                        if cell % 2 == 0 { color_a } else { color_b }
+                    }
+                    TexturePattern::UvDebug =>
+                    {   //格子の線は2色目、マスの中はUVのグラデーション(市松に明るさを変える)
+                        let ( cu, cv ) = ( u * period as f32, v * period as f32 );
+                        let is_line = cu.fract() < 0.03 || cv.fract() < 0.03;
+                        let shade = if ( cu as i32 + cv as i32 ) % 2 == 0 { 1.0 } else { 0.7 };
+                        if is_line { color_b } else { mix( Color::rgb( u, v, 1.0 - u ), color_a, 0.2 ) * shade }
+                    }
+                };
+                data.extend_from_slice( &color.as_rgba_u8() );
+            }
+        }
+        data
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//整数の座標とシードから0.0～1.0の値を作る(同じ入力なら環境によらず同じ値)
+fn hash( x: i32, y: i32, seed: u64 ) -> f32
+{   let mut h = seed
+        ^ ( x as u32 as u64 ).wrapping_mul( 0x9E37_79B9_7F4A_7C15 )
+        ^ ( y as u32 as u64 ).wrapping_mul( 0xC2B2_AE3D_27D4_EB4F );
+    h ^= h >> 33;
+    h = h.wrapping_mul( 0xFF51_AFD7_ED55_8CCD );
+    h ^= h >> 33;
+    h = h.wrapping_mul( 0xC4CE_B9FE_1A85_EC53 );
+    h ^= h >> 33;
+    ( h >> 40 ) as f32 / ( 1u64 << 24 ) as f32
+}
+
+//格子の周期で繰り返すバリューノイズ(0.0～1.0)
+fn value_noise( x: f32, y: f32, period: ( i32, i32 ), seed: u64 ) -> f32
+{   let ( x0, y0 ) = ( x.floor(), y.floor() );
+    let ( tx, ty ) = ( ease_in_out( x - x0 ), ease_in_out( y - y0 ) );
+    let ( ix0, iy0 ) = ( ( x0 as i32 ).rem_euclid( period.0 ), ( y0 as i32 ).rem_euclid( period.1 ) );
+    let ( ix1, iy1 ) = ( ( ix0 + 1 ) % period.0, ( iy0 + 1 ) % period.1 );
+
+    let top    = hash( ix0, iy0, seed ) * ( 1.0 - tx ) + hash( ix1, iy0, seed ) * tx;
+    let bottom = hash( ix0, iy1, seed ) * ( 1.0 - tx ) + hash( ix1, iy1, seed ) * tx;
+    top * ( 1.0 - ty ) + bottom * ty
+}
+
+//周波数を倍々にしたノイズを重ねる(0.0～1.0)
+fn fbm( x: f32, y: f32, period: ( i32, i32 ), seed: u64 ) -> f32
+{   let ( mut sum, mut amplitude, mut total ) = ( 0.0, 1.0, 0.0 );
+    for octave in 0..PROCEDURAL_TEXTURE_OCTAVES
+    {   let scale = ( 1 << octave ) as f32;
+        let period = ( period.0 << octave, period.1 << octave );
+        sum += value_noise( x * scale, y * scale, period, seed.wrapping_add( octave as u64 ) ) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+    }
+    sum / total
+}
+
+//------------------------------------------------------------------------------
+
+#[cfg( test )]
+mod tests
+{   use super::*;
+
+    use std::{ collections::hash_map::DefaultHasher, hash::{ Hash, Hasher } };
+
+    const ALL_PATTERNS: [ TexturePattern; 5 ] =
+    [   TexturePattern::WoodGrain,
+        TexturePattern::BrushedMetal,
+        TexturePattern::Grass,
+        TexturePattern::Checker,
+        TexturePattern::UvDebug,
+    ];
+
+    fn desc( pattern: TexturePattern, seed: u64 ) -> TextureDesc
+    {   TextureDesc { pattern, seed, scale: 4.0, colors: Vec::new(), size: 32 }
+    }
+
+    fn pixel_hash( desc: &TextureDesc ) -> u64
+    {   let mut hasher = DefaultHasher::new();
+        desc.pixels().hash( &mut hasher );
+        hasher.finish()
+    }
+
+    //同じ設定なら何度作っても同じピクセルになる
+    #[test]
+    fn pixels_are_deterministic()
+    {   for pattern in ALL_PATTERNS
+        {   let desc = desc( pattern, 7 );
+            assert!( desc.validate().is_ok() );
+            assert_eq!( desc.pixels().len(), 32 * 32 * 4, "{pattern:?}" );
+            assert_eq!( pixel_hash( &desc ), pixel_hash( &desc.clone() ), "{pattern:?}" );
+        }
+    }
+
+    //ノイズを使う模様はシードが違えば違う画像になる(市松模様とUV確認用はシードを使わない)
+    #[test]
+    fn seed_changes_noise_patterns()
+    {   for pattern in ALL_PATTERNS
+        {   let is_seeded = ! matches!( pattern, TexturePattern::Checker | TexturePattern::UvDebug );
+            let is_changed = desc( pattern, 1 ).pixels() != desc( pattern, 2 ).pixels();
+            assert_eq!( is_changed, is_seeded, "{pattern:?}" );
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use super::*;
+
+//------------------------------------------------------------------------------
+
+//地面の目盛りと座標軸の表示の状態
+#[derive( Resource, Default )]
+pub struct ReferenceGrid { pub is_visible: bool }
+
+//------------------------------------------------------------------------------
+
+//[G]で地面の目盛りと座標軸の表示を切り換える
+pub fn toggle_reference_grid
+(   mut grid: ResMut<ReferenceGrid>,
+    inkey: Res<Input<KeyCode>>,
+)
+{   if inkey.just_pressed( KeyCode::G ) { grid.is_visible = ! grid.is_visible }
+}
+
+//地面(Y=0)の目盛りと原点の座標軸を描く
+//目盛りの間隔はカメラの距離rの桁に合わせて変わり、注目点から離れるほど薄くなる
+pub fn draw_reference_grid
+(   mut gizmos: Gizmos,
+    grid: Res<ReferenceGrid>,
+    q_camera: Query<&OrbitCamera>,
+)
+{   if ! grid.is_visible { return }
+    let Ok ( camera ) = q_camera.get_single() else { return };
+
+    //rの桁に合わせた間隔(次の桁に近づくほど細かい目盛りを薄くする)
+    let exponent = camera.orbit.r.max( f32::EPSILON ).log10() + GRID_SPACING_EXPONENT;
+    let spacing = 10.0_f32.powf( exponent.floor() );
+    let minor_alpha = 1.0 - ( exponent - exponent.floor() );
+    let major = spacing * GRID_MAJOR_EVERY as f32;
+
+    //注目点の真下を中心にする(大きな目盛りに揃えて、注目点が動いても線がずれないようにする)
+    let focus = camera.focus.xz();
+    let center = ( focus / major ).round() * major;
+    let cells = GRID_HALF_CELLS as i32;
+    let fade_radius = ( GRID_HALF_CELLS as f32 - GRID_MAJOR_EVERY as f32 * 0.5 ) * spacing;
+
+    //注目点からの距離で薄くした色
+    let faded = | color: Color, point: Vec2 |
+    {   let t = ( 1.0 - point.distance( focus ) / fade_radius ).clamp( 0.0, 1.0 );
+        color.with_a( color.a() * t * t )
+    };
+    let to_vec3 = | point: Vec2 | Vec3::new( point.x, GRID_Y_OFFSET, point.y );
+
+    for i in -cells..=cells
+    {   let color = if i % GRID_MAJOR_EVERY as i32 == 0 { GRID_MAJOR_COLOR }
+            else { GRID_MINOR_COLOR.with_a( GRID_MINOR_COLOR.a() * minor_alpha ) };
+
+        //X方向とZ方向の線を、色を変えられるように短く区切って描く
+        for ( axis, across ) in [ ( Vec2::X, Vec2::Y ), ( Vec2::Y, Vec2::X ) ]
+        {   let line_center = center + across * i as f32 * spacing;
+            for j in -cells..cells
+            {   let start = line_center + axis * j as f32 * spacing;
+                let end   = start + axis * spacing;
+                gizmos.line_gradient( to_vec3( start ), to_vec3( end ), faded( color, start ), faded( color, end ) );
+            }
+        }
+    }
+
+    //原点の座標軸(X:赤 Y:緑 Z:青)
+    let length = major;
+    gizmos.line( Vec3::ZERO, Vec3::X * length, Color::RED   );
+    gizmos.line( Vec3::ZERO, Vec3::Y * length, Color::GREEN );
+    gizmos.line( Vec3::ZERO, Vec3::Z * length, Color::BLUE  );
+}
@@ -0,0 +1,162 @@
+use super::*;
+
+use std::collections::HashMap;
+use bevy::{ pbr::wireframe::WireframeConfig, render::mesh::VertexAttributeValues };
+use hud_message::HudMessage;
+
+//------------------------------------------------------------------------------
+
+//3Dビューポートの表示モード([R]で順に切り換える)
+#[derive( Clone, Copy, PartialEq, Default, Debug )]
+pub enum RenderMode
+{   #[default]
+    Shaded,           //通常の表示
+    WireframeOverlay, //通常の表示にワイヤーフレームを重ねる
+    WireframeOnly,    //ワイヤーフレームだけ
+    Normals,          //通常の表示に頂点法線の線を重ねる
+    Unlit,            //ライティングなしの基本色
+}
+
+impl RenderMode
+{   const ALL: [ RenderMode; 5 ] =
+    [   RenderMode::Shaded,
+        RenderMode::WireframeOverlay,
+        RenderMode::WireframeOnly,
+        RenderMode::Normals,
+        RenderMode::Unlit,
+    ];
+
+    //前後のモード
+    fn step( self, is_back: bool ) -> Self
+    {   let len = Self::ALL.len();
+        let index = Self::ALL.iter().position( | mode | *mode == self ).unwrap_or( 0 );
+        Self::ALL[ if is_back { ( index + len - 1 ) % len } else { ( index + 1 ) % len } ]
+    }
+
+    //HUDに表示する名前
+    fn name( self ) -> &'static str
+    {   match self
+        {   RenderMode::Shaded           => "shaded",
+            RenderMode::WireframeOverlay => "wireframe overlay",
+            RenderMode::WireframeOnly    => "wireframe only",
+            RenderMode::Normals          => "vertex normals",
+            RenderMode::Unlit            => "unlit base color",
+        }
+    }
+}
+
+//表示モードの状態と、差し替えたマテリアルのキャッシュ
+#[derive( Resource, Default )]
+pub struct RenderModes
+{   pub mode: RenderMode,
+    unlit: HashMap<Handle<StandardMaterial>, Handle<StandardMaterial>>, //元のマテリアル→ライティングなしのマテリアル
+    invisible: Option<Handle<StandardMaterial>>,                        //ワイヤーフレームだけの時の透明なマテリアル
+}
+
+//マテリアルを差し替えたエンティティに付けて、元のマテリアルを覚えておくComponent
+#[derive( Component )]
+pub struct BaseMaterial ( pub Handle<StandardMaterial> );
+
+//------------------------------------------------------------------------------
+
+//[R]で表示モードを切り換える([Shift]+[R]で逆順)
+pub fn cycle_render_mode
+(   mut modes: ResMut<RenderModes>,
+    mut wireframe: ResMut<WireframeConfig>,
+    mut message: ResMut<HudMessage>,
+    inkey: Res<Input<KeyCode>>,
+    time: Res<Time>,
+)
+{   if ! inkey.just_pressed( KeyCode::R ) { return }
+
+    let is_shift = inkey.any_pressed( [ KeyCode::ShiftLeft, KeyCode::ShiftRight ] );
+    modes.mode = modes.mode.step( is_shift );
+    wireframe.global = matches!( modes.mode, RenderMode::WireframeOverlay | RenderMode::WireframeOnly );
+    message.show( format!( "Render mode: {}", modes.mode.name() ), &time );
+}
+
+//表示モードに合わせてメッシュのマテリアルを差し替える
+//(後から作られたメッシュやファイルから読み込んだメッシュも、見つけ次第差し替える)
+pub fn apply_render_mode
+(   mut modes: ResMut<RenderModes>,
+    mut q_swapped: Query<( Entity, &mut Handle<StandardMaterial>, &BaseMaterial )>,
+    mut q_meshes: Query<( Entity, &mut Handle<StandardMaterial> ), ( With<Handle<Mesh>>, Without<BaseMaterial> )>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut cmds: Commands,
+)
+{   let modes = &mut *modes;
+    let is_swapped = matches!( modes.mode, RenderMode::WireframeOnly | RenderMode::Unlit );
+
+    //通常のマテリアルへ戻す
+    if ! is_swapped
+    {   for ( entity, mut handle, base ) in q_swapped.iter_mut()
+        {   *handle = base.0.clone();
+            cmds.entity( entity ).remove::<BaseMaterial>();
+        }
+        modes.unlit.clear(); //元のマテリアルを離す
+        return
+    }
+
+    //差し替え先のマテリアル
+    let mut variant = | base: &Handle<StandardMaterial>, materials: &mut Assets<StandardMaterial> |
+    {   if modes.mode == RenderMode::WireframeOnly
+        {   let invisible = modes.invisible.get_or_insert_with( ||
+                materials.add
+                (   StandardMaterial
+                    {   base_color: Color::NONE,
+                        alpha_mode: AlphaMode::Blend,
+                        unlit: true,
+                        ..default()
+                    }
+                )
+            );
+            return Some ( invisible.clone() )
+        }
+        if let Some ( unlit ) = modes.unlit.get( base ) { return Some ( unlit.clone() ) }
+        let unlit = StandardMaterial { unlit: true, ..materials.get( base )?.clone() }; //読込中なら次のフレームでやり直す
+        let unlit = materials.add( unlit );
+        modes.unlit.insert( base.clone(), unlit.clone() );
+        Some ( unlit )
+    };
+
+    //差し替え済みのメッシュはモードに合わせ直し、まだのメッシュは元のマテリアルを覚えてから差し替える
+    for ( _, mut handle, base ) in q_swapped.iter_mut()
+    {   if let Some ( swapped ) = variant( &base.0, &mut materials )
+        {   if *handle != swapped { *handle = swapped }
+        }
+    }
+    for ( entity, mut handle ) in q_meshes.iter_mut()
+    {   let Some ( swapped ) = variant( &handle, &mut materials ) else { continue };
+        cmds.entity( entity ).insert( BaseMaterial ( handle.clone() ) );
+        *handle = swapped;
+    }
+}
+
+//頂点法線の線を描く(線が多すぎる時は上限で打ち切る)
+pub fn draw_vertex_normals
+(   mut gizmos: Gizmos,
+    modes: Res<RenderModes>,
+    q_camera: Query<&OrbitCamera>,
+    q_meshes: Query<( &Handle<Mesh>, &GlobalTransform, &ComputedVisibility )>,
+    meshes: Res<Assets<Mesh>>,
+)
+{   if modes.mode != RenderMode::Normals { return }
+    let Ok ( camera ) = q_camera.get_single() else { return };
+    let length = camera.orbit.r * RENDER_MODE_NORMAL_LENGTH;
+
+    let mut count = 0;
+    for ( handle, transform, visibility ) in q_meshes.iter()
+    {   if ! visibility.is_visible() { continue }
+        let Some ( mesh ) = meshes.get( handle ) else { continue };
+        let Some ( VertexAttributeValues::Float32x3 ( positions ) ) = mesh.attribute( Mesh::ATTRIBUTE_POSITION ) else { continue };
+        let Some ( VertexAttributeValues::Float32x3 ( normals ) ) = mesh.attribute( Mesh::ATTRIBUTE_NORMAL ) else { continue };
+
+        for ( position, normal ) in positions.iter().zip( normals )
+        {   if count >= RENDER_MODE_MAX_NORMALS { return }
+            count += 1;
+            let start = transform.transform_point( Vec3::from( *position ) );
+            let direction = transform.affine().transform_vector3( Vec3::from( *normal ) ).normalize_or_zero();
+            gizmos.line( start, start + direction * length, RENDER_MODE_NORMAL_COLOR );
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use super::*;
+
+use std::{ fmt, fs, path::{ Path, PathBuf } };
+use serde::de::{ DeserializeOwned, DeserializeSeed };
+use bevy::asset::FileAssetIo;
+use ron::ser::PrettyConfig;
+
+//------------------------------------------------------------------------------
+
+//RONファイルの読み書きで起きるエラー
+#[derive( Debug )]
+pub enum RonFileError
+{   Io    ( PathBuf, std::io::Error ),            //ファイルが開けない・書けない
+    Parse ( PathBuf, ron::error::SpannedError ),  //書式が正しくない(行と列の情報付き)
+    Write ( PathBuf, ron::Error ),                //RONへ変換できない
+}
+
+impl fmt::Display for RonFileError
+{   fn fmt( &self, f: &mut fmt::Formatter ) -> fmt::Result
+    {   match self
+        {   Self::Io    ( path, err ) => write!( f, "{}: {err}", path.display() ),
+            Self::Parse ( path, err ) => write!( f, "{}:{err}", path.display() ),
+            Self::Write ( path, err ) => write!( f, "{}: {err}", path.display() ),
+        }
+    }
+}
+
+impl std::error::Error for RonFileError {}
+
+//------------------------------------------------------------------------------
+
+//assetsフォルダの中のファイルのパスを作る
+pub fn asset_path( file: &str ) -> PathBuf
+{   FileAssetIo::get_base_path().join( ASSETS_DIR ).join( file )
+}
+
+//RONファイルを読み込む
+pub fn load<T: DeserializeOwned>( path: &Path ) -> Result<T, RonFileError>
+{   let text = fs::read_to_string( path )
+        .map_err( | err | RonFileError::Io( path.to_path_buf(), err ) )?;
+
+    ron::de::from_str( &text )
+        .map_err( | err | RonFileError::Parse( path.to_path_buf(), err ) )
+}
+
+//RONファイルを型情報(DeserializeSeed)を使って読み込む
+pub fn load_seed<S, T>( path: &Path, seed: S ) -> Result<T, RonFileError>
+where S: for<'de> DeserializeSeed<'de, Value = T>
+{   let text = fs::read_to_string( path )
+        .map_err( | err | RonFileError::Io( path.to_path_buf(), err ) )?;
+
+    let mut deserializer = ron::de::Deserializer::from_str( &text )
+        .map_err( | err | RonFileError::Parse( path.to_path_buf(), err ) )?;
+    seed.deserialize( &mut deserializer )
+        .map_err( | err | RonFileError::Parse( path.to_path_buf(), deserializer.span_error( err ) ) )
+}
+
+//RONファイルへ書き出す(フォルダがなければ作る)
+pub fn save<T: Serialize>( path: &Path, value: &T ) -> Result<(), RonFileError>
+{   let text = ron::ser::to_string_pretty( value, PrettyConfig::default() )
+        .map_err( | err | RonFileError::Write( path.to_path_buf(), err ) )?;
+
+    save_text( path, &text )
+}
+
+//RONに変換済みの文字列をファイルへ書き出す(フォルダがなければ作る)
+pub fn save_text( path: &Path, text: &str ) -> Result<(), RonFileError>
+{   if let Some ( dir ) = path.parent()
+    {   fs::create_dir_all( dir )
+            .map_err( | err | RonFileError::Io( path.to_path_buf(), err ) )?;
+    }
+    fs::write( path, text )
+        .map_err( | err | RonFileError::Io( path.to_path_buf(), err ) )
+}
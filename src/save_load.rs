@@ -0,0 +1,245 @@
+use super::*;
+
+use std::collections::{ HashMap, HashSet };
+use bevy::
+{   ecs::entity::EntityMap,
+    hierarchy::despawn_with_children_recursive,
+    scene::{ DynamicEntity, serde::SceneDeserializer },
+};
+use app_state::{ SceneList, SceneEntity };
+use hud_message::HudMessage;
+use ron_file::RonFileError;
+
+//------------------------------------------------------------------------------
+
+//保存したエンティティに付ける名前のパス(ルートからNameを"/"でつないだもの)
+//読込時は同じパスのエンティティへ状態を書き戻す。保存ファイルの中にしか存在しない
+#[derive( Component, Reflect, Default )]
+#[reflect( Component )]
+pub struct SavePath ( pub String );
+
+//保存ファイルの見出し(どのシーンの状態か)。保存ファイルの中にしか存在しない
+#[derive( Resource, Reflect, Default )]
+#[reflect( Resource )]
+pub struct SaveHeader { pub scene: String }
+
+//読み込んで、シーンを作り直した後に書き戻すのを待っている状態
+#[derive( Resource )]
+pub struct PendingLoad ( DynamicScene );
+
+//------------------------------------------------------------------------------
+
+//シーンの状態の保存と読込のキー操作
+//  [F5]:保存 [F9]:読込(シーンを作り直してから状態を書き戻す)
+pub fn save_or_load( world: &mut World )
+{   let inkey = world.resource::<Input<KeyCode>>();
+    let is_save = inkey.just_pressed( KeyCode::F5 );
+    let is_load = inkey.just_pressed( KeyCode::F9 );
+
+    let file = ron_file::asset_path( SCENE_STATE_FILE );
+    let message = if is_save
+    {   match save( world, &file )
+        {   Ok  ( () ) => { info!( "scene state saved: {}", file.display() ); "Scene state saved" }
+            Err ( err ) => { error!( "failed to save scene state: {err}" ); "Failed to save" }
+        }
+    }
+    else if is_load
+    {   match load( world, &file )
+        {   Ok  ( () ) => return, //書き戻した時にメッセージを出す
+            Err ( err ) => { error!( "failed to load scene state: {err}" ); "Failed to load" }
+        }
+    }
+    else { return };
+
+    show_message( world, message );
+}
+
+//読み込んだ状態を作り直したシーンへ書き戻す
+//(シーンのspawnのCommandsが反映された後、Transformが伝播される前に実行する)
+pub fn apply_pending_load( world: &mut World )
+{   let Some ( PendingLoad ( saved ) ) = world.remove_resource::<PendingLoad>() else { return };
+
+    //保存ファイルと今のシーンのエンティティをパスで対応付ける
+    let live_paths = named_paths( world );
+    let live: HashMap<&str, Entity> =
+        live_paths.iter().map( | ( entity, path, _ ) | ( path.as_str(), *entity ) ).collect();
+    let mut entity_map = EntityMap::default();
+    let mut entities = Vec::new();
+    let mut saved_paths = HashSet::new();
+    for saved_entity in &saved.entities
+    {   let Some ( path ) = saved_path( saved_entity ) else { continue };
+        saved_paths.insert( path.clone() );
+
+        //保存後に名前が変わったエンティティは無視する
+        let Some ( &entity ) = live.get( path.as_str() ) else { continue };
+        entity_map.insert( saved_entity.entity, entity );
+        entities.push( DynamicEntity
+        {   entity: saved_entity.entity,
+            components: saved_entity.components.iter()
+                .filter( | component | ! is_type::<SavePath>( &***component ) )
+                .map( | component | component.clone_value() )
+                .collect(),
+        });
+
+        //保存した時に無かったComponentを外す(開けた錠前・抽選済みの中身・追従の解除)
+        remove_if_unsaved::<lock_key::Locked>( world, entity, saved_entity );
+        remove_if_unsaved::<loot::Loot>( world, entity, saved_entity );
+        remove_if_unsaved::<camera_follow::CameraTarget>( world, entity, saved_entity );
+    }
+    let resources = saved.resources.iter()
+        .filter( | resource | ! is_type::<SaveHeader>( &***resource ) )
+        .map( | resource | resource.clone_value() )
+        .collect();
+
+    //Vecは書き戻しても短くならないので、空にしてから書き戻す
+    *world.resource_mut::<lock_key::KeyRing>() = default();
+    *world.resource_mut::<inventory::Inventory>() = default();
+
+    let scene = DynamicScene { resources, entities };
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    if let Err ( err ) = scene.write_to_world_with( world, &mut entity_map, &registry )
+    {   error!( "failed to apply scene state: {err}" );
+        show_message( world, "Failed to load" );
+        return
+    }
+    world.resource_mut::<day_night::DayNight>().stop_after_load();
+
+    //保存した時に無かった鍵と錠前(拾った鍵・開けた錠前)を消す
+    for ( entity, path, _ ) in &live_paths
+    {   let is_removable = world.get::<lock_key::ChestKey>( *entity ).is_some()
+            || world.get::<chest::ChestLock>( *entity ).is_some();
+        if is_removable && ! saved_paths.contains( path )
+        {   despawn_with_children_recursive( world, *entity );
+        }
+    }
+
+    info!( "scene state loaded" );
+    show_message( world, "Scene state loaded" );
+}
+
+//------------------------------------------------------------------------------
+
+//シーンの状態をファイルへ保存する
+fn save( world: &mut World, file: &std::path::Path ) -> Result<(), RonFileError>
+{   let targets: Vec<_> = named_paths( world ).into_iter()
+        .filter( | ( entity, _, root ) |
+            world.get::<SceneEntity>( *root ).is_some()
+                || world.get::<OrbitCamera>( *entity ).is_some()
+                || world.get::<light_rig::RigLight>( *entity ).is_some()
+        )
+        .collect();
+
+    //保存するComponentとResource
+    let mut builder = DynamicSceneBuilder::from_world( world );
+    builder
+        .deny_all()
+        .allow::<Transform>()
+        .allow::<OrbitCamera>()
+        .allow::<chest_lid::LidHinge>()
+        .allow::<lock_key::Locked>()
+        .allow::<lock_key::ChestKey>()
+        .allow::<loot::Loot>()
+        .allow::<treasure_hunt::HuntChest>()
+        .allow::<camera_follow::CameraTarget>()
+        .allow::<light_rig::RigLight>()
+        .allow::<light_rig::OrbitLight>()
+        .allow::<DirectionalLight>()
+        .allow::<PointLight>()
+        .allow::<SpotLight>()
+        .deny_all_resources()
+        .allow_resource::<lock_key::KeyRing>()
+        .allow_resource::<inventory::Inventory>()
+        .allow_resource::<treasure_hunt::HuntRound>()
+        .allow_resource::<catch_input::ZoomMode>()
+        .allow_resource::<catch_input::KeyboardSettings>()
+        .allow_resource::<AmbientLight>()
+        .allow_resource::<ClearColor>()
+        .allow_resource::<day_night::DayNight>()
+        .extract_entities( targets.iter().map( | ( entity, _, _ ) | *entity ) )
+        .extract_resources();
+    let mut scene = builder.build();
+
+    //読込時の対応付けに使うパスと、どのシーンの状態かを書き足す
+    let paths: HashMap<_, _> = targets.into_iter().map( | ( entity, path, _ ) | ( entity, path ) ).collect();
+    for dyn_entity in scene.entities.iter_mut()
+    {   let path = paths[ &dyn_entity.entity ].clone();
+        dyn_entity.components.push( Box::new( SavePath ( path ) ) );
+    }
+    let list = world.resource::<SceneList>();
+    let name = list.selected().map( | scene | scene.name() ).unwrap_or_default();
+    scene.resources.push( Box::new( SaveHeader { scene: name } ) );
+
+    let registry = world.resource::<AppTypeRegistry>();
+    let text = scene.serialize_ron( &registry.0 )
+        .map_err( | err | RonFileError::Write( file.to_path_buf(), err ) )?;
+    ron_file::save_text( file, &text )
+}
+
+//保存ファイルを読み込み、そのシーンを選んで作り直しを予約する
+fn load( world: &mut World, file: &std::path::Path ) -> Result<(), String>
+{   let scene =
+    {   let registry = world.resource::<AppTypeRegistry>().read();
+        let seed = SceneDeserializer { type_registry: &registry };
+        ron_file::load_seed( file, seed ).map_err( | err | err.to_string() )?
+    };
+
+    //どのシーンの状態か
+    let header = scene.resources.iter()
+        .filter( | resource | is_type::<SaveHeader>( &***resource ) )
+        .find_map( | resource | SaveHeader::from_reflect( &**resource ) )
+        .ok_or( format!( "{}: no SaveHeader", file.display() ) )?;
+    let mut list = world.resource_mut::<SceneList>();
+    let cursor = list.scenes.iter().position( | scene | scene.name() == header.scene )
+        .ok_or( format!( "{}: unknown scene {:?}", file.display(), header.scene ) )?;
+    list.cursor = cursor;
+
+    world.insert_resource( PendingLoad ( scene ) );
+    Ok ( () )
+}
+
+//------------------------------------------------------------------------------
+
+//ルートまで全部Nameが付いているエンティティの(エンティティ, パス, ルート)
+fn named_paths( world: &mut World ) -> Vec<( Entity, String, Entity )>
+{   let entities: Vec<_> = world.query_filtered::<Entity, With<Name>>().iter( world ).collect();
+    entities.into_iter()
+        .filter_map( | entity |
+        {   let mut names = Vec::new();
+            let mut current = entity;
+            loop
+            {   names.push( world.get::<Name>( current )?.as_str() );
+                match world.get::<Parent>( current )
+                {   Some ( parent ) => current = parent.get(),
+                    None => break,
+                }
+            }
+            names.reverse();
+            Some ( ( entity, names.join( "/" ), current ) )
+        })
+        .collect()
+}
+
+//保存ファイルのエンティティのパス
+fn saved_path( saved_entity: &DynamicEntity ) -> Option<String>
+{   saved_entity.components.iter()
+        .filter( | component | is_type::<SavePath>( &***component ) )
+        .find_map( | component | SavePath::from_reflect( &**component ) )
+        .map( | path | path.0 )
+}
+
+//リフレクションの値が型Tのものか(読み込んだ値はDynamicStructなどなので名前で比べる)
+fn is_type<T: Reflect>( value: &dyn Reflect ) -> bool
+{   value.type_name() == std::any::type_name::<T>()
+}
+
+//保存ファイルのエンティティに無いComponentを外す
+fn remove_if_unsaved<T: Component + Reflect>( world: &mut World, entity: Entity, saved_entity: &DynamicEntity )
+{   let is_saved = saved_entity.components.iter().any( | component | is_type::<T>( &**component ) );
+    if ! is_saved { world.entity_mut( entity ).remove::<T>(); }
+}
+
+//HUDにメッセージを出す
+fn show_message( world: &mut World, text: &str )
+{   let time = world.resource::<Time>().clone();
+    world.resource_mut::<HudMessage>().show( text, &time );
+}
@@ -0,0 +1,308 @@
+use super::*;
+
+use std::fmt;
+use ron_file::RonFileError;
+use chest::{ ChestSpec, SpawnTreasureChest };
+use material_library::MaterialLibrary;
+
+//------------------------------------------------------------------------------
+
+//シーンファイル(RON)の中身
+#[derive( Deserialize )]
+#[serde( deny_unknown_fields )]
+pub struct SceneDesc
+{   pub nodes: Vec<NodeDesc>,
+}
+
+//ノード(形・位置・マテリアルと子ノード)
+#[derive( Deserialize )]
+#[serde( deny_unknown_fields )]
+pub struct NodeDesc
+{   #[serde( default )]
+    pub name: Option<String>,           //名前(Nameコンポーネントになる)
+    #[serde( default )]
+    pub shape: Option<ShapeDesc>,       //形(なければ見えないノード)
+    #[serde( default )]
+    pub transform: TransformDesc,       //親ノードから見た位置・回転・拡縮
+    #[serde( default )]
+    pub material: Option<MaterialDesc>, //マテリアル(形がある時だけ使う)
+    #[serde( default )]
+    pub chest: Option<ChestSpec>,       //宝箱(形の代わりに宝箱を作る)
+    #[serde( default )]
+    pub followable: bool,               //[F]キーで追従できるか
+    #[serde( default )]
+    pub key: Option<String>,            //クリックで拾える鍵のID
+    #[serde( default )]
+    pub children: Vec<NodeDesc>,        //子ノード
+}
+
+//プリミティブの形
+#[derive( Deserialize )]
+#[serde( deny_unknown_fields )]
+pub enum ShapeDesc
+{   Plane    { size: f32 },
+    Box      { x: f32, y: f32, z: f32 },
+    Cube     { size: f32 },
+    Cylinder { radius: f32, height: f32, #[serde( default = "default_resolution" )] resolution: u32 },
+}
+
+fn default_resolution() -> u32 { shape::Cylinder::default().resolution }
+
+//位置・回転(XYZの順のオイラー角、度)・拡縮
+#[derive( Deserialize )]
+#[serde( default, deny_unknown_fields )]
+pub struct TransformDesc
+{   pub translation: [ f32; 3 ],
+    pub rotation   : [ f32; 3 ],
+    pub scale      : [ f32; 3 ],
+}
+
+impl Default for TransformDesc
+{   fn default() -> Self
+    {   Self { translation: [ 0.0; 3 ], rotation: [ 0.0; 3 ], scale: [ 1.0; 3 ] }
+    }
+}
+
+//マテリアル(色か、マテリアルライブラリの名前か、プロシージャルテクスチャ)
+#[derive( Clone, Debug, Deserialize )]
+pub enum MaterialDesc
+{   Rgb        ( f32, f32, f32 ),
+    Rgba       ( f32, f32, f32, f32 ),
+    Hex        ( String ),
+    Named      ( String ),                          //materials.ronで定義したマテリアル
+    Procedural ( procedural_texture::TextureDesc ), //プロシージャルテクスチャを貼ったマテリアル
+}
+
+//------------------------------------------------------------------------------
+
+//シーンファイルのエラー
+#[derive( Debug )]
+pub enum SceneFileError
+{   File    ( RonFileError ),                  //読めない・書式が正しくない
+    Invalid { node: String, message: String }, //書式は正しいが値がおかしい
+}
+
+impl fmt::Display for SceneFileError
+{   fn fmt( &self, f: &mut fmt::Formatter ) -> fmt::Result
+    {   match self
+        {   Self::File ( err ) => write!( f, "scene file: {err}" ),
+            Self::Invalid { node, message } => write!( f, "scene file: {node}: {message}" ),
+        }
+    }
+}
+
+impl std::error::Error for SceneFileError {}
+
+impl From<RonFileError> for SceneFileError
+{   fn from( err: RonFileError ) -> Self { Self::File( err ) }
+}
+
+//------------------------------------------------------------------------------
+
+//シーンファイルを読み込んで値をチェックする
+pub fn load( file: &str ) -> Result<SceneDesc, SceneFileError>
+{   let path = ron_file::asset_path( file );
+    let scene: SceneDesc = ron_file::load( &path )?;
+
+    for ( i, node ) in scene.nodes.iter().enumerate()
+    {   node.validate( &format!( "{file}: nodes[{i}]" ) )?;
+    }
+
+    Ok ( scene )
+}
+
+impl NodeDesc
+{   //ノードの値をチェックする(pathはエラー表示用のノードの場所)
+    fn validate( &self, path: &str ) -> Result<(), SceneFileError>
+    {   let path = match &self.name
+        {   Some ( name ) => format!( "{path} ({name})" ),
+            None => path.to_string(),
+        };
+        let invalid = | message: &str |
+            Err ( SceneFileError::Invalid { node: path.clone(), message: message.to_string() } );
+
+        //形の大きさ
+        let sizes = match &self.shape
+        {   Some ( ShapeDesc::Plane { size } ) => vec![ *size ],
+            Some ( ShapeDesc::Box { x, y, z } ) => vec![ *x, *y, *z ],
+            Some ( ShapeDesc::Cube { size } ) => vec![ *size ],
+            Some ( ShapeDesc::Cylinder { radius, height, resolution } ) =>
+            {   if *resolution < 3 { return invalid( "cylinder resolution must be 3 or more" ) }
+                vec![ *radius, *height ]
+            }
+            None => vec![],
+        };
+        if sizes.iter().any( | size | ! size.is_finite() || *size <= 0.0 )
+        {   return invalid( "shape sizes must be positive numbers" );
+        }
+
+        //位置・回転・拡縮
+        let transform = &self.transform;
+        let values = transform.translation.iter().chain( &transform.rotation ).chain( &transform.scale );
+        if values.any( | value | ! value.is_finite() )
+        {   return invalid( "transform values must be finite numbers" );
+        }
+
+        //宝箱
+        if let Some ( chest ) = &self.chest
+        {   if self.shape.is_some() { return invalid( "chest node cannot have a shape" ) }
+            if let Err ( message ) = chest.validate() { return invalid( &message ) }
+        }
+
+        //鍵
+        if let Some ( key_id ) = &self.key
+        {   if self.shape.is_none() { return invalid( "key needs a shape to be clicked" ) }
+            if key_id.is_empty() { return invalid( "key id must not be empty" ) }
+        }
+
+        //マテリアル
+        if self.material.is_some() && self.shape.is_none()
+        {   return invalid( "material needs a shape" );
+        }
+        if let Some ( material ) = &self.material
+        {   if let Err ( message ) = material.validate() { return invalid( &message ) }
+        }
+
+        //子ノード
+        for ( i, child ) in self.children.iter().enumerate()
+        {   child.validate( &format!( "{path}.children[{i}]" ) )?;
+        }
+
+        Ok (())
+    }
+}
+
+impl ShapeDesc
+{   //メッシュを作る
+    fn to_mesh( &self ) -> Mesh
+    {   match *self
+        {   Self::Plane { size } => shape::Plane::from_size( size ).into(),
+            Self::Box { x, y, z } => shape::Box::new( x, y, z ).into(),
+            Self::Cube { size } => shape::Cube::new( size ).into(),
+            Self::Cylinder { radius, height, resolution } =>
+                shape::Cylinder { radius, height, resolution, ..default() }.into(),
+        }
+    }
+}
+
+impl TransformDesc
+{   //Transformを作る
+    fn to_transform( &self ) -> Transform
+    {   let [ x, y, z ] = self.rotation.map( | degrees | degrees.to_radians() );
+        Transform::from_translation( Vec3::from( self.translation ) )
+            .with_rotation( Quat::from_euler( EulerRot::XYZ, x, y, z ) )
+            .with_scale( Vec3::from( self.scale ) )
+    }
+}
+
+impl MaterialDesc
+{   //値をチェックする(名前が定義されているかはspawnの時に調べる)
+    pub fn validate( &self ) -> Result<(), String>
+    {   match self
+        {   Self::Named ( name ) if name.is_empty() => Err ( "material name must not be empty".to_string() ),
+            Self::Named ( _ ) => Ok (()),
+            Self::Procedural ( texture ) => texture.validate(),
+            _ => self.to_color().map( | _ | () ),
+        }
+    }
+
+    //色を作る
+    pub fn to_color( &self ) -> Result<Color, String>
+    {   let color = match self
+        {   Self::Rgb  ( r, g, b    ) => Color::rgb ( *r, *g, *b ),
+            Self::Rgba ( r, g, b, a ) => Color::rgba( *r, *g, *b, *a ),
+            Self::Hex  ( hex ) =>
+                Color::hex( hex ).map_err( | err | format!( "bad hex color {hex:?}: {err}" ) )?,
+            Self::Named ( name ) => return Err ( format!( "named material {name:?} is not a color" ) ),
+            Self::Procedural ( _ ) => return Err ( "procedural texture is not a color".to_string() ),
+        };
+        let [ r, g, b, a ] = color.as_rgba_f32();
+        if [ r, g, b, a ].iter().any( | value | ! ( 0.0..=1.0 ).contains( value ) )
+        {   return Err ( "color values must be between 0.0 and 1.0".to_string() );
+        }
+        Ok ( color )
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//シーンをspawnする
+pub fn spawn
+(   cmds: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    images: &mut Assets<Image>,
+    library: &mut MaterialLibrary,
+    scene: &SceneDesc,
+)
+{   for node in &scene.nodes
+    {   let root = spawn_node( cmds, meshes, materials, images, library, node );
+        cmds.entity( root ).insert( app_state::SceneEntity ); //シーンの切換でdespawnする
+    }
+}
+
+//ノードと子ノードをspawnする
+fn spawn_node
+(   cmds: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    images: &mut Assets<Image>,
+    library: &mut MaterialLibrary,
+    node: &NodeDesc,
+) -> Entity
+{   let transform = node.transform.to_transform();
+    let chest = node.chest.as_ref().and_then( | spec | cmds.spawn_treasure_chest( spec.clone(), transform ) );
+    let entity = match chest
+    {   Some ( entity ) => entity,
+        None => cmds.spawn( SpatialBundle::from_transform( transform ) ).id(),
+    };
+    let mut entity_cmds = cmds.entity( entity );
+
+    //形があればメッシュとマテリアルを付ける(PbrBundleと同じ構成になる)
+    //マテリアルはライブラリで共有する
+    if let Some ( shape ) = &node.shape
+    {   let material = match &node.material
+        {   Some ( desc ) => library.handle( desc, materials, images ),
+            None => library.color( Color::default(), materials ),
+        };
+        entity_cmds
+            .insert( meshes.add( shape.to_mesh() ) )
+            .insert( material );
+    }
+
+    if let Some ( name ) = &node.name { entity_cmds.insert( Name::new( name.clone() ) ); }
+    if node.followable { entity_cmds.insert( camera_follow::Followable ); }
+    if let Some ( key_id ) = &node.key { entity_cmds.insert( lock_key::ChestKey { key_id: key_id.clone() } ); }
+
+    //子ノード
+    let children = node.children.iter()
+        .map( | child | spawn_node( cmds, meshes, materials, images, library, child ) )
+        .collect::<Vec<_>>();
+    cmds.entity( entity ).push_children( &children );
+
+    entity
+}
+
+//------------------------------------------------------------------------------
+
+#[cfg( test )]
+mod tests
+{   use super::*;
+
+    //assets/scenes/の全てのシーンファイルが読み込めて、値のチェックも通る
+    #[test]
+    fn all_scene_files_load()
+    {   let dir = ron_file::asset_path( "scenes" );
+        let entries = std::fs::read_dir( &dir ).unwrap_or_else( | err | panic!( "{}: {err}", dir.display() ) );
+
+        let mut count = 0;
+        for entry in entries
+        {   let path = entry.unwrap().path();
+            if path.extension().map_or( true, | ext | ext != "ron" ) { continue }
+            let file = format!( "scenes/{}", path.file_name().unwrap().to_string_lossy() );
+            if let Err ( err ) = load( &file ) { panic!( "{err}" ) }
+            count += 1;
+        }
+        assert!( count > 0, "no scene files in {}", dir.display() );
+    }
+}
@@ -0,0 +1,388 @@
+use super::*;
+
+use bevy::
+{   core_pipeline::Skybox,
+    pbr::EnvironmentMapLight,
+    render::render_resource::{ Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension },
+};
+use scene_file::MaterialDesc;
+
+//------------------------------------------------------------------------------
+
+//空の画像の出どころ
+#[derive( Clone, Deserialize )]
+pub enum SkySource
+{   Cubemap  ( String ), //6面を縦に並べた画像、またはKTX2のキューブマップ(assetsフォルダからの相対パス)
+    Equirect ( String ), //正距円筒図法の画像(HDR可。同上)
+    Gradient             //天頂・地平線・地面の3色のグラデーション(画像ファイル不要)
+    {   zenith : MaterialDesc,
+        horizon: MaterialDesc,
+        ground : MaterialDesc,
+    },
+}
+
+//スカイボックスの設定(skybox.ron)
+#[derive( Clone, Deserialize )]
+#[serde( default, deny_unknown_fields )]
+pub struct SkyboxDesc
+{   pub source           : SkySource,
+    pub environment_light: bool,           //PBRマテリアルの環境マップライティングにも使うか
+    pub diffuse_map      : Option<String>, //前処理済みの拡散光のキューブマップ(無ければ空の画像をそのまま使う)
+    pub specular_map     : Option<String>, //前処理済みの鏡面反射のキューブマップ(同上)
+}
+
+impl Default for SkyboxDesc
+{   fn default() -> Self
+    {   let [ zenith, horizon, ground ] = [ SKYBOX_ZENITH_COLOR, SKYBOX_HORIZON_COLOR, SKYBOX_GROUND_COLOR ]
+            .map( | color | { let [ r, g, b, _ ] = color.as_rgba_f32(); MaterialDesc::Rgb( r, g, b ) } );
+        Self
+        {   source           : SkySource::Gradient { zenith, horizon, ground },
+            environment_light: true,
+            diffuse_map      : None,
+            specular_map     : None,
+        }
+    }
+}
+
+//読込中の空の画像(読み込めたらキューブマップにする)
+enum PendingSky
+{   Cubemap  ( Handle<Image> ),
+    Equirect ( Handle<Image> ),
+}
+
+//スカイボックスの状態
+#[derive( Resource )]
+pub struct Sky
+{   pub is_enabled   : bool,                  //[O]で切り換える(無効ならClearColorで塗る。昼夜の移り変わりの間も同じ)
+    pending          : Option<PendingSky>,    //読込中の空の画像
+    cubemap          : Option<Handle<Image>>, //空のキューブマップ
+    environment_light: Option<( Option<Handle<Image>>, Option<Handle<Image>> )>, //環境マップライティング(拡散光・鏡面反射)
+}
+
+impl Default for Sky
+{   fn default() -> Self
+    {   Self { is_enabled: true, pending: None, cubemap: None, environment_light: None }
+    }
+}
+
+impl Sky
+{   //設定ファイルを読み込んで空の画像を用意する(無ければグラデーションの空)
+    fn load( &mut self, asset_server: &AssetServer, images: &mut Assets<Image> )
+    {   let file = ron_file::asset_path( SKYBOX_FILE );
+        let desc = if ! file.exists() { SkyboxDesc::default() } else
+        {   match ron_file::load::<SkyboxDesc>( &file )
+            {   Ok  ( desc ) => desc,
+                Err ( err  ) => { error!( "failed to load skybox: {err}" ); SkyboxDesc::default() }
+            }
+        };
+
+        self.cubemap = None;
+        self.pending = None;
+        match &desc.source
+        {   SkySource::Cubemap  ( path ) => self.pending = Some ( PendingSky::Cubemap ( asset_server.load( path.as_str() ) ) ),
+            SkySource::Equirect ( path ) => self.pending = Some ( PendingSky::Equirect ( asset_server.load( path.as_str() ) ) ),
+            SkySource::Gradient { zenith, horizon, ground } =>
+            {   let colors = [ zenith, horizon, ground ].map( | desc |
+                    desc.to_color().unwrap_or_else( | message | { error!( "{}: {message}", file.display() ); Color::BLACK } )
+                );
+                self.cubemap = Some ( images.add( gradient_cubemap( colors ) ) );
+            }
+        }
+
+        let load = | path: &Option<String> | path.as_ref().map( | path | asset_server.load( path.as_str() ) );
+        self.environment_light = desc.environment_light.then( || ( load( &desc.diffuse_map ), load( &desc.specular_map ) ) );
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//起動時に空の画像を用意する
+pub fn setup_skybox
+(   mut sky: ResMut<Sky>,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+)
+{   sky.load( &asset_server, &mut images );
+}
+
+//スカイボックスの操作
+//  [O]:スカイボックスのON/OFF [Shift]+[O]:設定ファイルの再読込
+pub fn toggle_skybox
+(   mut sky: ResMut<Sky>,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    inkey: Res<Input<KeyCode>>,
+)
+{   if ! inkey.just_pressed( KeyCode::O ) { return }
+
+    if inkey.any_pressed( [ KeyCode::ShiftLeft, KeyCode::ShiftRight ] )
+    {   sky.load( &asset_server, &mut images );
+        info!( "skybox reloaded" );
+    }
+    else
+    {   sky.is_enabled = ! sky.is_enabled;
+    }
+}
+
+//読み込めた空の画像をキューブマップにする
+pub fn prepare_skybox
+(   mut sky: ResMut<Sky>,
+    mut images: ResMut<Assets<Image>>,
+)
+{   let Some ( pending ) = &sky.pending else { return };
+    let handle = match pending { PendingSky::Cubemap ( handle ) | PendingSky::Equirect ( handle ) => handle.clone() };
+    let Some ( image ) = images.get_mut( &handle ) else { return }; //まだ読み込めていない
+
+    let cubemap = match pending
+    {   PendingSky::Cubemap ( _ ) =>
+        {   //KTX2のキューブマップはそのまま、6面を縦に並べた画像は6枚の配列として扱う
+            let size = image.texture_descriptor.size;
+            if size.depth_or_array_layers == 1 && size.height == size.width * 6
+            {   image.reinterpret_stacked_2d_as_array( 6 );
+            }
+            if image.texture_descriptor.size.depth_or_array_layers == 6
+            {   image.texture_view_descriptor = Some ( cube_view() );
+                Some ( handle )
+            }
+            else
+            {   error!( "skybox cubemap must be six faces stacked vertically" );
+                None
+            }
+        }
+        PendingSky::Equirect ( _ ) =>
+        {   let cubemap = equirect_cubemap( image );
+            if cubemap.is_none() { error!( "unsupported skybox image format {:?}", image.texture_descriptor.format ) }
+            cubemap.map( | cubemap | images.add( cubemap ) )
+        }
+    };
+    sky.pending = None;
+    sky.cubemap = cubemap;
+}
+
+//3Dカメラのスカイボックスと環境マップライティングを状態に合わせる
+//(昼夜の移り変わりの間は、昼の空で照らさないように外して、時刻の空の色のClearColorで塗る)
+pub fn apply_skybox
+(   sky: Res<Sky>,
+    day_night: Res<day_night::DayNight>,
+    q_camera: Query<( Entity, Option<&Skybox>, Option<&EnvironmentMapLight> ), With<OrbitCamera>>,
+    mut cmds: Commands,
+)
+{   let Ok ( ( entity, skybox, env_light ) ) = q_camera.get_single() else { return };
+    let cubemap = sky.cubemap.as_ref().filter( | _ | sky.is_enabled && ! day_night.is_enabled );
+
+    //スカイボックス(無い時はClearColorで塗られる)
+    match ( cubemap, skybox )
+    {   ( Some ( cubemap ), Some ( skybox ) ) if skybox.0 == *cubemap => (),
+        ( Some ( cubemap ), _ ) => { cmds.entity( entity ).insert( Skybox ( cubemap.clone() ) ); }
+        ( None, Some ( _ ) ) => { cmds.entity( entity ).remove::<Skybox>(); }
+        ( None, None ) => (),
+    }
+
+    //環境マップライティング(前処理済みの画像が無ければ空の画像で代用する)
+    let wanted = cubemap.zip( sky.environment_light.as_ref() ).map( | ( cubemap, ( diffuse, specular ) ) |
+        EnvironmentMapLight
+        {   diffuse_map : diffuse .clone().unwrap_or_else( || cubemap.clone() ),
+            specular_map: specular.clone().unwrap_or_else( || cubemap.clone() ),
+        }
+    );
+    match ( wanted, env_light )
+    {   ( Some ( wanted ), Some ( current ) )
+            if wanted.diffuse_map == current.diffuse_map && wanted.specular_map == current.specular_map => (),
+        ( Some ( wanted ), _ ) => { cmds.entity( entity ).insert( wanted ); }
+        ( None, Some ( _ ) ) => { cmds.entity( entity ).remove::<EnvironmentMapLight>(); }
+        ( None, None ) => (),
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//キューブマップとして見るためのTextureViewDescriptor
+fn cube_view() -> TextureViewDescriptor<'static>
+{   TextureViewDescriptor { dimension: Some ( TextureViewDimension::Cube ), ..default() }
+}
+
+//キューブマップの面(+X,-X,+Y,-Y,+Z,-Z)のテクセルの方向
+fn face_direction( face: usize, u: f32, v: f32 ) -> Vec3
+{   //u,vは-1.0～1.0(右・下が正)
+    let direction = match face
+    {   0 => Vec3::new(  1.0,   -v,   -u ),
+        1 => Vec3::new( -1.0,   -v,    u ),
+        2 => Vec3::new(    u,  1.0,    v ),
+        3 => Vec3::new(    u, -1.0,   -v ),
+        4 => Vec3::new(    u,   -v,  1.0 ),
+        _ => Vec3::new(   -u,   -v, -1.0 ),
+    };
+    direction.normalize()
+}
+
+//方向ごとの色(リニア)からキューブマップを作る(HDRを扱えるRgba16Float)
+fn cubemap_image( size: u32, color: impl Fn( Vec3 ) -> [ f32; 4 ] ) -> Image
+{   let mut data = Vec::with_capacity( ( size * size * 6 * 8 ) as usize );
+    for face in 0..6
+    {   for y in 0..size
+        {   for x in 0..size
+            {   let u = ( x as f32 + 0.5 ) / size as f32 * 2.0 - 1.0;
+                let v = ( y as f32 + 0.5 ) / size as f32 * 2.0 - 1.0;
+                for value in color( face_direction( face, u, v ) )
+                {   data.extend_from_slice( &f16_bits( value ).to_le_bytes() );
+                }
+            }
+        }
+    }
+
+    let extent = Extent3d { width: size, height: size, depth_or_array_layers: 6 };
+    let mut image = Image::new( extent, TextureDimension::D2, data, TextureFormat::Rgba16Float );
+    image.texture_view_descriptor = Some ( cube_view() );
+    image
+}
+
+//グラデーションの空のキューブマップ(天頂・地平線・地面の色)
+fn gradient_cubemap( [ zenith, horizon, ground ]: [ Color; 3 ] ) -> Image
+{   cubemap_image( SKYBOX_GRADIENT_SIZE, | direction |
+    {   let color = if direction.y >= 0.0
+        {   mix( horizon, zenith, direction.y.sqrt() )
+        }
+        else
+        {   mix( horizon, ground, ( -direction.y * SKYBOX_GROUND_BLEND ).min( 1.0 ) )
+        };
+        color.as_linear_rgba_f32()
+    })
+}
+
+//正距円筒図法の画像からキューブマップを作る(対応していない画像の形式ならNone)
+fn equirect_cubemap( image: &Image ) -> Option<Image>
+{   let ( width, height ) = ( image.texture_descriptor.size.width, image.texture_descriptor.size.height );
+    let format = image.texture_descriptor.format;
+    if ! matches!( format, TextureFormat::Rgba32Float | TextureFormat::Rgba8UnormSrgb ) { return None }
+
+    //テクセルの色(リニア)
+    let texel = | x: u32, y: u32 | -> [ f32; 4 ]
+    {   let index = ( y * width + x ) as usize;
+        if format == TextureFormat::Rgba32Float
+        {   let bytes = &image.data[ index * 16..index * 16 + 16 ];
+            std::array::from_fn( | i | f32::from_le_bytes( bytes[ i * 4..i * 4 + 4 ].try_into().unwrap() ) )
+        }
+        else
+        {   let bytes = &image.data[ index * 4..index * 4 + 4 ];
+            Color::rgba_u8( bytes[ 0 ], bytes[ 1 ], bytes[ 2 ], bytes[ 3 ] ).as_linear_rgba_f32()
+        }
+    };
+
+    let size = ( width / 4 ).clamp( 1, SKYBOX_EQUIRECT_MAX_FACE_SIZE );
+    Some ( cubemap_image( size, | direction |
+    {   //経度・緯度から画像の座標へ(-Zの方向が画像の中央)
+        let u = 0.5 + direction.x.atan2( -direction.z ) / TAU;
+        let v = 0.5 - direction.y.asin() / PI;
+        let x = ( ( u * width  as f32 ) as u32 ).min( width  - 1 );
+        let y = ( ( v * height as f32 ) as u32 ).min( height - 1 );
+        texel( x, y )
+    }))
+}
+
+//f32を半精度浮動小数点数のビット列にする(最近接偶数へ丸める。小さな値は非正規化数にする)
+fn f16_bits( value: f32 ) -> u16
+{   let bits = value.to_bits();
+    let sign = ( ( bits >> 16 ) & 0x8000 ) as u16;
+    let exponent = ( ( bits >> 23 ) & 0xff ) as i32 - 127 + 15;
+    let mantissa = bits & 0x7f_ffff;
+
+    if value.is_nan() { return sign | 0x7e00 }
+    if exponent >= 0x1f { return sign | 0x7c00 } //大きすぎる値は無限大
+    if exponent < -10 { return sign }            //非正規化数の最小値の半分より小さい値は0
+
+    //指数と仮数を並べて下位のビットを切り捨てる(非正規化数は暗黙の1を仮数に入れ、その分多く切り捨てる)
+    let ( value, shift ) = if exponent > 0 { ( ( ( exponent as u32 ) << 23 ) | mantissa, 13 ) }
+        else { ( mantissa | 0x80_0000, ( 14 - exponent ) as u32 ) };
+    let half = value >> shift;
+    let rest = value & ( ( 1 << shift ) - 1 );
+    let halfway = 1 << ( shift - 1 );
+    let is_round_up = rest > halfway || ( rest == halfway && half & 1 == 1 );
+
+    //繰り上がると指数も上がる(最大を超えれば無限大になる)
+    sign | ( half + u32::from( is_round_up ) ) as u16
+}
+
+//------------------------------------------------------------------------------
+
+#[cfg( test )]
+mod tests
+{   use super::*;
+
+    #[test]
+    fn f16_normal_values()
+    {   assert_eq!( f16_bits(  1.0     ), 0x3c00 );
+        assert_eq!( f16_bits( -2.0     ), 0xc000 );
+        assert_eq!( f16_bits(  0.5     ), 0x3800 );
+        assert_eq!( f16_bits(  65504.0 ), 0x7bff ); //最大値
+        assert_eq!( f16_bits(  0.0     ), 0x0000 );
+        assert_eq!( f16_bits( -0.0     ), 0x8000 );
+    }
+
+    //最近接偶数への丸め
+    #[test]
+    fn f16_rounding()
+    {   let ulp = 2.0_f32.powi( -10 ); //1.0の近くの半精度の刻み
+        assert_eq!( f16_bits( 1.0 + ulp * 0.5  ), 0x3c00 ); //ちょうど中間は偶数へ(切り捨て)
+        assert_eq!( f16_bits( 1.0 + ulp * 1.5  ), 0x3c02 ); //ちょうど中間は偶数へ(切り上げ)
+        assert_eq!( f16_bits( 1.0 + ulp * 0.51 ), 0x3c01 ); //中間より上は切り上げ
+        assert_eq!( f16_bits( 1.0 + ulp * 0.49 ), 0x3c00 ); //中間より下は切り捨て
+        assert_eq!( f16_bits( 2.0 - ulp * 0.25 ), 0x4000 ); //仮数の繰り上がりで指数が上がる
+    }
+
+    //非正規化数
+    #[test]
+    fn f16_subnormals()
+    {   assert_eq!( f16_bits( 2.0_f32.powi( -14 ) ), 0x0400 );        //正規化数の最小値
+        assert_eq!( f16_bits( 2.0_f32.powi( -15 ) ), 0x0200 );
+        assert_eq!( f16_bits( 2.0_f32.powi( -24 ) ), 0x0001 );        //非正規化数の最小値
+        assert_eq!( f16_bits( -2.0_f32.powi( -24 ) ), 0x8001 );
+        assert_eq!( f16_bits( 2.0_f32.powi( -24 ) * 1.5 ), 0x0002 );  //ちょうど中間は偶数へ
+        assert_eq!( f16_bits( 2.0_f32.powi( -25 ) ), 0x0000 );        //最小値の半分は偶数(0)へ
+        assert_eq!( f16_bits( 2.0_f32.powi( -25 ) * 1.01 ), 0x0001 ); //最小値の半分より上は最小値へ
+        assert_eq!( f16_bits( 2.0_f32.powi( -30 ) ), 0x0000 );
+        assert_eq!( f16_bits( 2.0_f32.powi( -14 ) * 0.9999 ), 0x0400 ); //非正規化数から正規化数へ繰り上がる
+    }
+
+    //無限大とNaN
+    #[test]
+    fn f16_infinity_and_nan()
+    {   assert_eq!( f16_bits( f32::INFINITY     ), 0x7c00 );
+        assert_eq!( f16_bits( f32::NEG_INFINITY ), 0xfc00 );
+        assert_eq!( f16_bits( 1.0e6             ), 0x7c00 );
+        assert_eq!( f16_bits( 65520.0           ), 0x7c00 ); //最大値と次の中間は無限大へ丸める
+        assert_eq!( f16_bits( 65519.0           ), 0x7bff );
+
+        let nan = f16_bits( f32::NAN );
+        assert_eq!( nan & 0x7c00, 0x7c00 );
+        assert_ne!( nan & 0x03ff, 0 );
+    }
+
+    //キューブマップの各面(+X,-X,+Y,-Y,+Z,-Z)の中心・右・下の方向
+    #[test]
+    fn cube_face_orientation()
+    {   let faces =
+        [   ( Vec3::X    , Vec3::NEG_Z, Vec3::NEG_Y ),
+            ( Vec3::NEG_X, Vec3::Z    , Vec3::NEG_Y ),
+            ( Vec3::Y    , Vec3::X    , Vec3::Z     ),
+            ( Vec3::NEG_Y, Vec3::X    , Vec3::NEG_Z ),
+            ( Vec3::Z    , Vec3::X    , Vec3::NEG_Y ),
+            ( Vec3::NEG_Z, Vec3::NEG_X, Vec3::NEG_Y ),
+        ];
+        for ( face, ( center, right, down ) ) in faces.into_iter().enumerate()
+        {   let near = | a: Vec3, b: Vec3 | a.distance( b ) < 1e-5;
+            assert!( near( face_direction( face, 0.0, 0.0 ), center ), "face {face} center" );
+            assert!( near( face_direction( face, 1.0, 0.0 ), ( center + right ).normalize() ), "face {face} right" );
+            assert!( near( face_direction( face, 0.0, 1.0 ), ( center + down  ).normalize() ), "face {face} down" );
+        }
+    }
+
+    //隣り合う面の境目は同じ方向になる
+    #[test]
+    fn cube_face_edges_meet()
+    {   let near = | a: Vec3, b: Vec3 | a.distance( b ) < 1e-5;
+        assert!( near( face_direction( 0, -1.0, 0.0 ), face_direction( 4, 1.0, 0.0 ) ) ); //+Xの左と+Zの右
+        assert!( near( face_direction( 0, 0.0, -1.0 ), face_direction( 2, 1.0, 0.0 ) ) ); //+Xの上と+Yの右
+        assert!( near( face_direction( 4, 0.0, -1.0 ), face_direction( 2, 0.0, 1.0 ) ) ); //+Zの上と+Yの下
+        assert!( near( face_direction( 5, -1.0, 0.0 ), face_direction( 0, 1.0, 0.0 ) ) ); //-Zの左と+Xの右
+    }
+}
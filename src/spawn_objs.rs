@@ -1,6 +1,6 @@
 use super::*;
 
-//3Dカメラと光源を作る
+//3Dカメラを作る(光源はlight_rigで作る)
 pub fn camera3d_and_light
 (   q_window: Query<&Window>,
     mut cmds: Commands,
@@ -15,25 +15,15 @@ pub fn camera3d_and_light
 
     //3Dカメラ
     let orbit_camera = OrbitCamera::default();
-    let vec3 = orbit_camera.orbit.into_vec3();
-    cmds.spawn( ( Camera3dBundle::default(), orbit_camera ) )
+    let focus = orbit_camera.focus;
+    let vec3 = focus + orbit_camera.orbit.into_vec3();
+    let projection = PerspectiveProjection { fov: CAMERA_INIT_FOV, ..default() }.into();
+    cmds.spawn( ( Camera3dBundle { projection, ..default() }, orbit_camera ) )
         .insert( Camera { order: CAMERA3D_ORDER, viewport, ..default() } )
+        .insert( Name::new( "camera" ) ) //シーンの状態の保存・読込で使う
         .insert
-        (   Transform::from_translation( vec3 )    //カメラの位置
-                .looking_at( Vec3::ZERO, Vec3::Y ) //カメラレンズの向き
-        );
-
-    //光源
-    let light = DirectionalLight
-    {   illuminance: LIGHT_BRIGHTNESS,
-        shadows_enabled: true, //影の描画を有効化
-        ..default()
-    };
-    cmds.spawn( DirectionalLightBundle::default() )
-        .insert( light )
-        .insert
-        (   Transform::from_translation( LIGHT_POSITION ) //光源の位置
-                .looking_at( Vec3::ZERO, Vec3::Z )        //光源の向き
+        (   Transform::from_translation( vec3 ) //カメラの位置
+                .looking_at( focus, Vec3::Y )   //カメラレンズの向き
         );
 }
 
@@ -70,59 +60,23 @@ pub fn display_board
 //------------------------------------------------------------------------------
 
 //3Dオブジェクトを作る(宝箱)
+//形や位置はシーンファイルに書かれている
 pub fn locked_chest
-(   mut cmds: Commands,
+(   list: Res<app_state::SceneList>,
+    mut cmds: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut library: ResMut<material_library::MaterialLibrary>,
 )
-{  
-    //地面
-    cmds.spawn(PbrBundle::default())
-    .insert(meshes.add(shape::Plane::from_size(2.0).into()))
-    .insert(Transform::from_translation(Vec3::new(0.0, 0.0, 0.0))) // ギズモの中心に合わせる
-    .insert(materials.add(Color::rgb(0.5, 0.7, 0.3).into()));
+{   //宝箱のシーンを選んだ時だけ作る
+    if list.selected() != Some ( &app_state::ViewerScene::LockedChest ) { return }
 
-    //宝箱
-    cmds.spawn(PbrBundle::default())
-    .insert(materials.add(Color::NONE.into())) //透明
-    .insert(Transform::from_translation(Vec3::new(0.0, 0.5, 0.0))) // ギズモの中心に合わせる
-    .with_children(|cmds| {
-        //本体
-        let shape_box = shape::Box::new(0.7, 0.3, 0.4);
-        cmds.spawn(PbrBundle::default())
-            .insert(meshes.add(shape_box.into()))
-            .insert(Transform::from_translation(Vec3::new(0.0, -0.35, 0.0))) // ギズモの中心に合わせる
-            .insert(materials.add(Color::MAROON.into()));
-
-        //上蓋
-        let shape_cylinder = shape::Cylinder {
-            height: 0.695,
-            radius: 0.195,
-            ..default()
-        };
-        cmds.spawn(PbrBundle::default())
-            .insert(meshes.add(shape_cylinder.into()))
-            .insert(
-                Transform::from_translation(Vec3::new(0.0, -0.2, 0.0))
-                    .with_rotation(Quat::from_rotation_z(PI * 0.5)),
-            )
-            .insert(materials.add(Color::MAROON.into()));
-
-        //錠前
-        let shape_cube = shape::Cube::new(0.1);
-        cmds.spawn(PbrBundle::default())
-            .insert(meshes.add(shape_cube.into()))
-            .insert(Transform::from_translation(Vec3::new(0.0, -0.2, 0.17))) // ギズモの中心に合わせる
-            .insert(materials.add(Color::GRAY.into()))
-            .with_children(|cmds| {
-                //鍵穴
-                // ...
+    //シーンファイルを読み込む(エラーならログに出して何も作らない)
+    let scene = match scene_file::load( LOCKED_CHEST_SCENE_FILE )
+    {   Ok  ( scene ) => scene,
+        Err ( err   ) => { error!( "{err}" ); return }
+    };
 
-                let shape_box = shape::Box::new(0.01, 0.04, 0.11);
-                cmds.spawn(PbrBundle::default())
-                    .insert(meshes.add(shape_box.into()))
-                    .insert(Transform::from_translation(Vec3::new(0.0, 0.0, 0.0))) // ギズモの中心に合わせる
-                    .insert(materials.add(Color::BLACK.into()));
-            });
-    });
-}
\ No newline at end of file
+    scene_file::spawn( &mut cmds, &mut meshes, &mut materials, &mut images, &mut library, &scene );
+}
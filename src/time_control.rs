@@ -0,0 +1,81 @@
+use super::*;
+
+//------------------------------------------------------------------------------
+
+//コマ送りの状態
+#[derive( Default, PartialEq )]
+enum FrameStep
+{   #[default]
+    Idle,      //コマ送りしていない
+    Requested, //このフレームの最後に時間を動かし始める
+    Running,   //このフレームだけ時間が進む
+}
+
+//コマ送り(一時停止中に1フレームだけ時間を進める)の管理
+#[derive( Resource, Default )]
+pub struct TimeControl
+{   step: FrameStep,
+}
+
+//------------------------------------------------------------------------------
+
+//一時停止の画面に入ったら時間を止める
+pub fn pause_time( mut time: ResMut<Time> )
+{   time.pause();
+}
+
+//一時停止の画面から出たら時間を動かす
+pub fn resume_time
+(   mut time: ResMut<Time>,
+    mut control: ResMut<TimeControl>,
+)
+{   time.unpause();
+    control.step = FrameStep::Idle;
+}
+
+//時間の速さを変える(一時停止中も変えられる)
+//  [-]:遅く [=]:速く [0]:等速
+pub fn change_time_scale
+(   mut time: ResMut<Time>,
+    inkey: Res<Input<KeyCode>>,
+)
+{   let speed = time.relative_speed();
+    let index = TIME_SCALES.iter().position( | scale | *scale == speed );
+
+    let speed = if inkey.just_pressed( KeyCode::Key0 ) { 1.0 }
+        else if inkey.just_pressed( KeyCode::Minus )
+        {   index.map_or( 1.0, | index | TIME_SCALES[ index.saturating_sub( 1 ) ] )
+        }
+        else if inkey.just_pressed( KeyCode::Equals )
+        {   index.map_or( 1.0, | index | TIME_SCALES[ ( index + 1 ).min( TIME_SCALES.len() - 1 ) ] )
+        }
+        else { return };
+
+    time.set_relative_speed( speed );
+}
+
+//一時停止中に[N]で1フレームだけ時間を進める
+pub fn request_frame_step
+(   mut time: ResMut<Time>,
+    mut control: ResMut<TimeControl>,
+    inkey: Res<Input<KeyCode>>,
+)
+{   if ! inkey.just_pressed( KeyCode::N ) || ! time.is_paused() { return }
+    if control.step != FrameStep::Idle { return }
+
+    //次のフレームの時間の更新で進むように、ここで止めるのをやめる
+    time.unpause();
+    control.step = FrameStep::Requested;
+}
+
+//コマ送りで進めたフレームが終わったら、また時間を止める(Lastで実行する)
+pub fn finish_frame_step
+(   mut time: ResMut<Time>,
+    mut control: ResMut<TimeControl>,
+)
+{   control.step = match control.step
+    {   FrameStep::Idle      => FrameStep::Idle,
+        FrameStep::Requested => FrameStep::Running,
+        FrameStep::Running   => { time.pause(); FrameStep::Idle }
+    };
+}
@@ -0,0 +1,165 @@
+use super::*;
+
+use rand::{ Rng, SeedableRng, rngs::StdRng };
+use chest::{ ChestSpec, SpawnTreasureChest };
+use chest_lid::LidOpened;
+use hud_message::HudMessage;
+use launch_options::LaunchOptions;
+use app_state::{ SceneList, SceneEntity, ViewerScene };
+use material_library::MaterialLibrary;
+use scene_file::MaterialDesc;
+
+//------------------------------------------------------------------------------
+
+//宝探しモードの宝箱に付けるComponent
+#[derive( Component, Reflect, Default )]
+#[reflect( Component )]
+pub struct HuntChest
+{   is_found : bool,
+    is_opened: bool,
+}
+
+//宝探しの進み具合(宝探しモードの時だけ存在する)
+#[derive( Resource, Reflect )]
+#[reflect( Resource )]
+pub struct HuntRound
+{   pub total   : usize, //宝箱の数
+    pub found   : usize, //発見した数
+    pub opened  : usize, //開けた数
+    elapsed_secs: f32,   //経過時間(一時停止中は止まり、全部開けたら止める)
+    is_finished : bool,  //全部開けたか
+}
+
+impl HuntRound
+{   //得点(終わっていたら早さのボーナスを足す)
+    pub fn score( &self ) -> u32
+    {   let bonus = if ! self.is_finished { 0.0 }
+            else { ( HUNT_PAR_SECS - self.elapsed_secs ).max( 0.0 ) * HUNT_BONUS_PER_SEC };
+        self.found as u32 * HUNT_FOUND_SCORE + self.opened as u32 * HUNT_OPENED_SCORE + bonus as u32
+    }
+
+    //HUDに表示する状態
+    pub fn status( &self ) -> String
+    {   let secs = self.elapsed_secs as u32;
+        let clear = if self.is_finished { " CLEAR!" } else { "" };
+        format!
+        (   " hunt: found {}/{} opened {}/{}\n score:{} time:{:02}:{:02}{clear}",
+            self.found, self.total, self.opened, self.total, self.score(), secs / 60, secs % 60,
+        )
+    }
+}
+
+//------------------------------------------------------------------------------
+
+//広い地面に宝箱を重ならないようにばらまく(シードが同じなら同じ配置になる)
+pub fn spawn_hunt
+(   list: Res<SceneList>,
+    launch: Res<LaunchOptions>,
+    mut cmds: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut library: ResMut<MaterialLibrary>,
+)
+{   //宝探しモードを選んだ時だけ作る
+    let Some ( ViewerScene::TreasureHunt ( count ) ) = list.selected() else { return };
+    let count = *count;
+
+    //地面
+    cmds.spawn( PbrBundle
+    {   mesh: meshes.add( shape::Plane::from_size( HUNT_GROUND_SIZE ).into() ),
+        material: library.handle( &MaterialDesc::Named( "grass".to_string() ), &mut materials, &mut images ),
+        ..default()
+    })
+    .insert( ( Name::new( "ground" ), SceneEntity ) );
+
+    //宝箱の位置を決める(近すぎたらやり直す)
+    let mut rng = match launch.seed
+    {   Some ( seed ) => StdRng::seed_from_u64( seed ),
+        None => StdRng::from_entropy(),
+    };
+    let half = ( HUNT_GROUND_SIZE - HUNT_CHEST_GAP ) * 0.5; //地面からはみ出さない範囲
+    let mut positions: Vec<Vec3> = Vec::with_capacity( count );
+    for _ in 0..count.saturating_mul( HUNT_PLACE_TRIES )
+    {   if positions.len() == count { break }
+
+        let position = Vec3::new( rng.gen_range( -half..=half ), 0.0, rng.gen_range( -half..=half ) );
+        if positions.iter().any( | placed | placed.distance( position ) < HUNT_CHEST_GAP ) { continue }
+        positions.push( position );
+    }
+    if positions.len() < count
+    {   warn!( "only {} of {count} chests could be placed", positions.len() );
+    }
+
+    //宝箱(鍵はかけない)
+    for ( i, position ) in positions.iter().enumerate()
+    {   let rotation = Quat::from_rotation_y( rng.gen_range( 0.0..TAU ) );
+        let transform = Transform::from_translation( *position ).with_rotation( rotation );
+        let spec = ChestSpec { locked: false, ..default() };
+        let Some ( chest ) = cmds.spawn_treasure_chest( spec, transform ) else { continue };
+        cmds.entity( chest ).insert( ( HuntChest::default(), SceneEntity, Name::new( format!( "chest {}", i + 1 ) ) ) );
+    }
+
+    cmds.insert_resource
+    (   HuntRound
+        {   total       : positions.len(),
+            found       : 0,
+            opened      : 0,
+            elapsed_secs: 0.0,
+            is_finished : false,
+        }
+    );
+}
+
+//------------------------------------------------------------------------------
+
+//時間を進めて、注目点が近づいた宝箱を発見済みにする
+pub fn find_hunt_chests
+(   q_camera: Query<&OrbitCamera>,
+    mut q_chest: Query<( &mut HuntChest, &GlobalTransform )>,
+    round: Option<ResMut<HuntRound>>,
+    mut message: ResMut<HudMessage>,
+    time: Res<Time>,
+)
+{   let Some ( mut round ) = round else { return };
+    if ! round.is_finished { round.elapsed_secs += time.delta_seconds() }
+    let Ok ( camera ) = q_camera.get_single() else { return };
+
+    for ( mut chest, transform ) in q_chest.iter_mut()
+    {   if chest.is_found { continue }
+        if transform.translation().distance( camera.focus ) > HUNT_FIND_RADIUS { continue }
+
+        chest.is_found = true;
+        round.found += 1;
+        message.show( "Found a chest!", &time );
+    }
+}
+
+//開いた宝箱を数えて、全部開いたら終了する
+pub fn count_opened_chests
+(   mut q_chest: Query<&mut HuntChest>,
+    round: Option<ResMut<HuntRound>>,
+    mut e_opened: EventReader<LidOpened>,
+    mut message: ResMut<HudMessage>,
+    time: Res<Time>,
+)
+{   let Some ( mut round ) = round else { return };
+
+    for opened in e_opened.iter()
+    {   let Ok ( mut chest ) = q_chest.get_mut( opened.chest ) else { continue };
+        if chest.is_opened { continue }
+
+        //見つける前に開けた(遠くからクリックした)場合も発見に数える
+        if ! chest.is_found
+        {   chest.is_found = true;
+            round.found += 1;
+        }
+        chest.is_opened = true;
+        round.opened += 1;
+
+        //全部開けたら時間を止める
+        if round.opened < round.total || round.is_finished { continue }
+        round.is_finished = true;
+        message.show( format!( "All chests opened! score:{}", round.score() ), &time );
+    }
+}